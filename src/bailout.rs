@@ -0,0 +1,93 @@
+//! The test that decides when an orbit has escaped.
+
+use num::complex::Complex;
+
+/// How the size of `z` is measured when it is compared against the bailout radius.
+///
+/// Only [`Norm::Euclidean`] gives the mathematically correct set. The others escape the points at
+/// the edges of a different shape, which changes the bands around the set for artistic effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Norm {
+    /// |z|, the distance from origo. Escapes outside a circle.
+    #[default]
+    Euclidean,
+    /// |re| + |im|. Escapes outside a diamond.
+    Manhattan,
+    /// max(|re|, |im|). Escapes outside a square.
+    Chebyshev,
+    /// |re| only. Escapes outside a vertical strip.
+    Real,
+    /// |im| only. Escapes outside a horizontal strip.
+    Imaginary,
+}
+
+impl Norm {
+    pub const ALL: [Norm; 5] = [
+        Norm::Euclidean,
+        Norm::Manhattan,
+        Norm::Chebyshev,
+        Norm::Real,
+        Norm::Imaginary,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Norm::Euclidean => "euclidean",
+            Norm::Manhattan => "manhattan",
+            Norm::Chebyshev => "chebyshev",
+            Norm::Real => "real",
+            Norm::Imaginary => "imaginary",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Norm> {
+        Norm::ALL.into_iter().find(|norm| norm.name() == name)
+    }
+}
+
+/// When `z` counts as escaped: once its size, measured by `norm`, is larger than `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bailout {
+    pub radius: f64,
+    pub norm: Norm,
+}
+
+impl Bailout {
+    pub fn new(radius: f64, norm: Norm) -> Bailout {
+        Bailout { radius, norm }
+    }
+
+    /// The same bailout, with the radius raised to at least `radius`.
+    pub fn with_min_radius(self, radius: f64) -> Bailout {
+        Bailout::new(self.radius.max(radius), self.norm)
+    }
+
+    /// Whether the orbits of all points in the Mandelbrot set stay inside this bailout.
+    ///
+    /// Those orbits never leave the disc |z| <= 2. All the norms measure z as at most |z|,
+    /// except Manhattan, which can measure it as up to √2 times as much.
+    pub fn contains_set(&self) -> bool {
+        match self.norm {
+            Norm::Manhattan => self.radius >= 2.0 * std::f64::consts::SQRT_2,
+            _ => self.radius >= 2.0,
+        }
+    }
+
+    pub fn escaped(&self, z: Complex<f64>) -> bool {
+        match self.norm {
+            // compare squared lengths, which saves a square root per iteration.
+            Norm::Euclidean => z.norm_sqr() > self.radius * self.radius,
+            Norm::Manhattan => z.re.abs() + z.im.abs() > self.radius,
+            Norm::Chebyshev => z.re.abs().max(z.im.abs()) > self.radius,
+            Norm::Real => z.re.abs() > self.radius,
+            Norm::Imaginary => z.im.abs() > self.radius,
+        }
+    }
+}
+
+impl Default for Bailout {
+    /// |z| > 2: once z is further than 2 from origo, it is certain to speed off into infinity.
+    fn default() -> Bailout {
+        Bailout::new(2.0, Norm::Euclidean)
+    }
+}
@@ -0,0 +1,95 @@
+//! Named [`Location`]s, kept in a plain text file.
+//!
+//! Every line holds one bookmark: its name, then the location as written by [`Location`]'s
+//! `Display`. Empty lines and lines starting with `#` are skipped.
+//!
+//! ```text
+//! # rusty-mandelbrot bookmarks
+//! seahorse center=-0.75,0.1 zoom=20 iterations=2000 palette=ocean
+//! ```
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::location::Location;
+
+/// The bookmarks from one file, in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookmarks {
+    entries: Vec<(String, Location)>,
+}
+
+impl Bookmarks {
+    /// Where bookmarks are kept unless told otherwise: `.mandelbrot-bookmarks` in the home
+    /// directory, or in the current directory if there is no home.
+    pub fn default_path() -> PathBuf {
+        let home = env::var_os("HOME").map_or_else(PathBuf::new, PathBuf::from);
+        home.join(".mandelbrot-bookmarks")
+    }
+
+    /// Reads the bookmarks from `path`. A file that does not exist yet has no bookmarks in it.
+    pub fn load(path: &Path) -> Result<Bookmarks, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(Bookmarks::default())
+            }
+            Err(error) => return Err(format!("could not read {}: {}", path.display(), error)),
+        };
+
+        let mut bookmarks = Bookmarks::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, location) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let location = location
+                .parse()
+                .map_err(|message| format!("{} line {}: {}", path.display(), index + 1, message))?;
+            bookmarks.insert(name, location)?;
+        }
+        Ok(bookmarks)
+    }
+
+    /// Writes all bookmarks to `path`, replacing what was there.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = String::from("# rusty-mandelbrot bookmarks: name, then the location\n");
+        for (name, location) in &self.entries {
+            text.push_str(&format!("{} {}\n", name, location));
+        }
+        fs::write(path, text)
+            .map_err(|error| format!("could not write {}: {}", path.display(), error))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Location> {
+        self.entries
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, location)| location)
+    }
+
+    /// Adds a bookmark, or moves an existing one with the same name. Names cannot be empty or
+    /// contain whitespace, since the name ends at the first space in the file.
+    pub fn insert(&mut self, name: &str, location: Location) -> Result<(), String> {
+        if name.is_empty() || name.contains(char::is_whitespace) || name.starts_with('#') {
+            return Err(format!(
+                "invalid bookmark name '{}': it cannot be empty, start with # or contain spaces",
+                name
+            ));
+        }
+        match self.entries.iter_mut().find(|(other, _)| other == name) {
+            Some((_, existing)) => *existing = location,
+            None => self.entries.push((name.to_string(), location)),
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Location)> {
+        self.entries
+            .iter()
+            .map(|(name, location)| (name.as_str(), location))
+    }
+}
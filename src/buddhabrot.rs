@@ -0,0 +1,346 @@
+//! The Buddhabrot: where the orbits of escaping points travel, rather than how long they take.
+//!
+//! [`calculate_mandelbrot`](crate::calculate_mandelbrot) keeps only the number of iterations of
+//! each orbit. The Buddhabrot traces the orbits of random points instead, and counts how often
+//! they pass through each pixel. The counts are then tone-mapped into a brightness per pixel, and
+//! come back in the same grid form as the iteration counts, with a [`Density`] per pixel.
+//!
+//! ```
+//! use rusty_mandelbrot::buddhabrot::{calculate_buddhabrot, Buddhabrot};
+//! use rusty_mandelbrot::{Config, View};
+//!
+//! let config = Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 30, 20), 100);
+//! let buddhabrot = Buddhabrot { samples: 10_000, ..Buddhabrot::default() };
+//! let density = calculate_buddhabrot(&config, &buddhabrot, 2);
+//!
+//! assert!(density.iter().flatten().any(|point| point.channels[0] == 1.0));
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use num::complex::Complex;
+
+use crate::formula::{
+    Buffalo, BurningShip, Celtic, Formula, Fractal, Mandelbrot, Multibrot, Perpendicular, Tricorn,
+};
+use crate::mandelbrot::{is_in_cardioid_or_bulb, EscapeValue};
+use crate::palette::Palette;
+use crate::parallel;
+use crate::view::{Config, View};
+
+/// How to sample the orbits of a Buddhabrot, and which of them to count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buddhabrot {
+    /// The number of random points whose orbits are traced. More points give a less grainy
+    /// picture, and take longer.
+    pub samples: usize,
+    /// The iteration limits of the red, green and blue channels, for a Nebulabrot. Without them,
+    /// all three use the config's `max_iterations`, and the palette colours the picture.
+    pub nebula: Option<[usize; 3]>,
+    /// Count the orbits that do not escape instead, for the anti-Buddhabrot.
+    pub anti: bool,
+    /// Where the random numbers start. The same seed gives the same picture, whatever the number
+    /// of threads.
+    pub seed: u64,
+}
+
+impl Default for Buddhabrot {
+    fn default() -> Buddhabrot {
+        Buddhabrot {
+            samples: 1_000_000,
+            nebula: None,
+            anti: false,
+            seed: 1,
+        }
+    }
+}
+
+impl Buddhabrot {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.samples == 0 {
+            return Err("a Buddhabrot needs at least 1 sample".to_string());
+        }
+        if self.nebula.is_some_and(|limits| limits.contains(&0)) {
+            return Err("the Nebulabrot iterations must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The iteration limit of each channel: the Nebulabrot's, or `max_iterations` for all three.
+    pub fn limits(&self, max_iterations: usize) -> [usize; 3] {
+        self.nebula.unwrap_or([max_iterations; 3])
+    }
+}
+
+/// How bright a single pixel of a Buddhabrot is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Density {
+    /// The brightness of the red, green and blue channels, from 0 to 1. All three are the same,
+    /// unless this is a Nebulabrot.
+    pub channels: [f64; 3],
+    /// Whether the channels are the colours of a Nebulabrot, rather than a brightness to look up
+    /// in the palette.
+    pub nebula: bool,
+    /// The average brightness as an iteration count below `max_iterations`, for the output that
+    /// works with iteration counts, like ascii or the greyscale images.
+    pub level: f64,
+}
+
+impl EscapeValue for Density {
+    fn to_f64(self) -> f64 {
+        self.level
+    }
+
+    fn color(self, palette: Palette) -> Option<[u8; 3]> {
+        if self.nebula {
+            Some(self.channels.map(|channel| (channel * 255.0).round() as u8))
+        } else {
+            Some(palette.color(self.channels[0]))
+        }
+    }
+}
+
+impl fmt::Display for Density {
+    /// The brightness, or the brightness of each channel separated by commas for a Nebulabrot.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [red, green, blue] = self.channels;
+        if self.nebula {
+            write!(f, "{:.4},{:.4},{:.4}", red, green, blue)
+        } else {
+            write!(f, "{:.4}", red)
+        }
+    }
+}
+
+/// Traces the orbits of `buddhabrot.samples` random points, spread over `threads` threads, and
+/// returns how bright each pixel of `config.view` is. The points are picked around the default
+/// view of `config.formula`, and iterated with its bailout. Julia sets have no Buddhabrot here, so
+/// `config.julia` is not used.
+///
+/// The grid is indexed as `points[pixel_y][pixel_x]`, as for
+/// [`calculate_mandelbrot`](crate::calculate_mandelbrot).
+pub fn calculate_buddhabrot(
+    config: &Config,
+    buddhabrot: &Buddhabrot,
+    threads: usize,
+) -> Vec<Vec<Density>> {
+    let view = &config.view;
+    let pixels = view.width * view.height;
+    // one count per pixel and channel, added to by every thread at once. A pixel that most
+    // orbits pass through can go past 2^32 in a long render, and an atomic add would wrap around
+    // without a word, so the counts have 64 bits.
+    let histogram: Vec<AtomicU64> = (0..3 * pixels).map(|_| AtomicU64::new(0)).collect();
+
+    // the samples are split into fixed chunks, each with random numbers of its own, so the
+    // picture does not depend on which thread gets which chunk.
+    let chunks = buddhabrot.samples.div_ceil(CHUNK_SAMPLES);
+    parallel::for_each(chunks, threads, |chunk| {
+        let samples = CHUNK_SAMPLES.min(buddhabrot.samples - chunk * CHUNK_SAMPLES);
+        let mut random = Random::new(buddhabrot.seed, chunk as u64);
+        trace_fractal(config, buddhabrot, samples, &mut random, &histogram);
+    });
+
+    let counts: Vec<u64> = histogram.into_iter().map(AtomicU64::into_inner).collect();
+    let channels: Vec<Vec<f64>> = counts.chunks(pixels.max(1)).map(tone_map).collect();
+    let nebula = buddhabrot.nebula.is_some();
+    let top_level = config.max_iterations.saturating_sub(1) as f64;
+
+    (0..view.height)
+        .map(|pixel_y| {
+            (0..view.width)
+                .map(|pixel_x| {
+                    let index = pixel_y * view.width + pixel_x;
+                    let channels = [0, 1, 2]
+                        .map(|channel| channels.get(channel).map_or(0.0, |values| values[index]));
+                    Density {
+                        channels,
+                        nebula,
+                        level: channels.iter().sum::<f64>() / 3.0 * top_level,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// the number of samples in each piece of work handed to a thread.
+const CHUNK_SAMPLES: usize = 4096;
+
+/// Turns the number of orbits through each pixel into a brightness from 0 to 1.
+///
+/// The counts are measured against a high percentile of them rather than the highest, since a
+/// few pixels that nearly all orbits cross would leave the rest in the dark. An exposure curve
+/// then lifts the faint trails of the rarer orbits, and eases the brightest parts into white
+/// instead of cutting them off.
+pub fn tone_map(counts: &[u64]) -> Vec<f64> {
+    let mut lit: Vec<u64> = counts.iter().copied().filter(|&count| count > 0).collect();
+    if lit.is_empty() {
+        return vec![0.0; counts.len()];
+    }
+    let index = (lit.len() - 1) * 9999 / 10000;
+    let reference = *lit.select_nth_unstable(index).1 as f64;
+    counts
+        .iter()
+        .map(|&count| {
+            let exposure = count as f64 / reference;
+            ((1.0 - (-2.0 * exposure).exp()) / (1.0 - (-2.0f64).exp())).min(1.0)
+        })
+        .collect()
+}
+
+// picks the formula once per chunk, so the loop in `trace` is compiled for each on its own, as
+// `iterate_fractal` does for the escape-time loop.
+fn trace_fractal(
+    config: &Config,
+    buddhabrot: &Buddhabrot,
+    samples: usize,
+    random: &mut Random,
+    histogram: &[AtomicU64],
+) {
+    let mut tracer = Tracer {
+        config,
+        buddhabrot,
+        samples,
+        random,
+        histogram,
+    };
+    match config.formula {
+        Fractal::Custom(ref expression) => tracer.trace(&**expression),
+        Fractal::Mandelbrot => tracer.trace(&Mandelbrot),
+        Fractal::Multibrot(power) => tracer.trace(&Multibrot { power }),
+        Fractal::BurningShip => tracer.trace(&BurningShip),
+        Fractal::Tricorn => tracer.trace(&Tricorn),
+        Fractal::Celtic => tracer.trace(&Celtic),
+        Fractal::Buffalo => tracer.trace(&Buffalo),
+        Fractal::Perpendicular => tracer.trace(&Perpendicular),
+    }
+}
+
+// everything one chunk of samples needs.
+struct Tracer<'a> {
+    config: &'a Config,
+    buddhabrot: &'a Buddhabrot,
+    samples: usize,
+    random: &'a mut Random,
+    histogram: &'a [AtomicU64],
+}
+
+impl Tracer<'_> {
+    fn trace<F: Formula>(&mut self, formula: &F) {
+        let config = self.config;
+        let view = &config.view;
+        let limits = self.buddhabrot.limits(config.max_iterations);
+        let longest = limits.into_iter().max().unwrap_or(0);
+        let anti = self.buddhabrot.anti;
+        let region = sampling_region(&formula.default_view());
+
+        // points inside the cardioid and the bulb never escape, so their orbits are never counted
+        // in a Buddhabrot. They are the most of an anti-Buddhabrot, though.
+        let skip_interior = !anti
+            && config.interior_shortcut
+            && formula.has_mandelbrot_interior()
+            && config.bailout.contains_set();
+
+        let mut orbit = Vec::with_capacity(longest);
+        for _ in 0..self.samples {
+            let c = Complex::new(
+                region.real_min + self.random.next_f64() * (region.real_max - region.real_min),
+                region.imaginary_min
+                    + self.random.next_f64() * (region.imaginary_max - region.imaginary_min),
+            );
+            let mut z = formula.start(Complex::new(0.0, 0.0), c, c);
+            if skip_interior && z == Complex::new(0.0, 0.0) && is_in_cardioid_or_bulb(c) {
+                continue;
+            }
+
+            // the orbit up to where it escapes: `escaped` is the number of points before that.
+            orbit.clear();
+            let mut escaped = None;
+            for i in 0..longest {
+                z = formula.step_at(z, c, c, i);
+                if config.bailout.escaped(z) {
+                    escaped = Some(orbit.len());
+                    break;
+                }
+                orbit.push(z);
+            }
+
+            for (channel, &limit) in limits.iter().enumerate() {
+                // with a lower limit, a point that escapes late counts as one that never does.
+                let points = match escaped {
+                    Some(points) if points < limit && !anti => points,
+                    Some(points) if points >= limit && anti => limit,
+                    None if anti => limit,
+                    _ => continue,
+                };
+                for &point in &orbit[..points] {
+                    if let Some(pixel) = pixel_of(view, point) {
+                        self.histogram[channel * view.width * view.height + pixel]
+                            .fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// the default view of a formula fits its fractal snugly. The points that make up the Buddhabrot
+// are on the edge of it, so they are picked from a quarter further out on every side.
+fn sampling_region(view: &View) -> View {
+    let margin_x = (view.real_max - view.real_min) / 4.0;
+    let margin_y = (view.imaginary_max - view.imaginary_min) / 4.0;
+    View::new(
+        view.real_min - margin_x,
+        view.real_max + margin_x,
+        view.imaginary_min - margin_y,
+        view.imaginary_max + margin_y,
+        view.width,
+        view.height,
+    )
+}
+
+// the index of the pixel a point falls in, the other way around from `View::pixel_to_complex`.
+fn pixel_of(view: &View, point: Complex<f64>) -> Option<usize> {
+    let x = (point.re - view.real_min) / (view.real_max - view.real_min) * view.width as f64;
+    let y = (point.im - view.imaginary_min) / (view.imaginary_max - view.imaginary_min)
+        * view.height as f64;
+    // NaN is in no range, so orbits that blew up are left out too.
+    if (0.0..view.width as f64).contains(&x) && (0.0..view.height as f64).contains(&y) {
+        Some(y as usize * view.width + x as usize)
+    } else {
+        None
+    }
+}
+
+/*
+SplitMix64, a small and fast generator of random numbers, good enough to pick points with.
+Each chunk of samples starts from its own state, mixed from the seed and the chunk's number, so
+no two chunks follow the same sequence.
+*/
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64, stream: u64) -> Random {
+        let mut mixer = Random { state: seed };
+        let mixed = mixer.next_u64() ^ stream.wrapping_mul(0xD1B5_4A32_D192_ED03);
+        Random {
+            state: Random { state: mixed }.next_u64(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // a number from 0 up to, but not including, 1, from the top 53 bits.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
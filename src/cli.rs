@@ -0,0 +1,1445 @@
+/*
+Parses the command-line arguments into the settings that used to be hard-coded in main.
+
+  rusty-mandelbrot [MODE] [OPTIONS]
+
+The first argument that is not an option selects the output mode, and modes that write a
+file take the path as the next one (png out.png). Every option takes exactly one value,
+given either as the next argument (--width 100) or inline (--width=100), except for the flags
+like --smooth that take none.
+The viewport can be given in one of two forms, but not both at the same time:
+
+  min/max form:     --real-min -2.0 --real-max 1.0 --imag-min -1.0 --imag-max 1.0
+  center+zoom form: --center -0.75,0.1 --zoom 20
+
+A bookmark (--bookmark NAME) or a preset from the built-in catalogue (--preset NAME) stands in
+for either form, and also brings its iterations and palette, unless -i or --palette are given
+as well.
+
+A scene file (--scene FILE) gives the starting value of everything it holds, including the mode
+and the exact bounds of the view. Any other option on the command line changes that value:
+--center and --zoom start from the scene's center and zoom, so --zoom alone zooms further in.
+A PNG written by this program holds its scene, so it can be given to --scene as well, in which
+case the mode and file are not taken from it, so the image is never overwritten by accident.
+
+--julia X,Y draws the Julia set of a point instead of the Mandelbrot set. Every other option
+works the same, and the view is placed the same way, only centered on zero by default.
+
+--newton, and the options that shape a Newton fractal (--roots, --polynomial, --relaxation,
+--nova), draw one instead of the escape-time formulas. Its view starts out around its roots.
+--buddhabrot, and the options that shape one (--nebulabrot, --anti-buddhabrot, --samples,
+--seed), draw where the orbits of random points go instead, with the same view and formula.
+
+The center+zoom form (also used when no bounds are given) widens the view along one axis to
+match the shape of the output, so the set is never stretched. The min/max form is taken as is.
+*/
+
+use std::path::{Path, PathBuf};
+
+use num::complex::Complex;
+use rusty_mandelbrot::bookmarks::Bookmarks;
+use rusty_mandelbrot::buddhabrot::Buddhabrot;
+use rusty_mandelbrot::newton::{self, Newton};
+use rusty_mandelbrot::presets::Preset;
+use rusty_mandelbrot::render::ansi::ColorDepth;
+use rusty_mandelbrot::render::ascii::Ramp;
+use rusty_mandelbrot::render::graphics::Protocol;
+use rusty_mandelbrot::scene::{Coloring, Output, Scene};
+use rusty_mandelbrot::terminal::{TerminalSize, DEFAULT_CELL_ASPECT};
+use rusty_mandelbrot::{parallel, Config, Formula, Fractal, Location, Norm, Palette, Scale, View};
+
+pub const USAGE: &str = "\
+Renders the Mandelbrot set.
+
+USAGE:
+    rusty-mandelbrot [MODE] [OPTIONS]
+    rusty-mandelbrot bookmarks [--bookmarks <FILE>]
+    rusty-mandelbrot list
+    rusty-mandelbrot --scene <FILE> [OPTIONS]
+
+MODES:
+    ascii               print the set as characters (default)
+    ansi                print the set as coloured cells, using ANSI escape sequences
+    halfblock           like ansi, with two points per character cell, one above the other
+    braille             print the set with braille dots, 2x4 points per character cell
+    explore             browse the set interactively: pan with the arrow keys, zoom with + and -,
+                        change the iterations with [ and ], the palette with p, quit with q.
+                        With the mouse: click to recenter, scroll to zoom at the cursor, and
+                        drag a rectangle to zoom into it. u and U undo and redo, b saves a
+                        bookmark and g jumps to one. J switches to the Julia set of the point
+                        in the center, and back, and f and F cycle through the formulas.
+                        It draws the plain iteration counts: --smooth and --periods are for
+                        the other modes, and the colouring of a scene is left out
+    image               draw the set as pixels, through the terminal's graphics protocol
+    raw                 print the iteration count of every pixel, one row per line
+    png <FILE>          write a true-colour PNG image, one pixel per point
+    ppm <FILE>          write a binary PPM image with 8 bits per channel
+    pgm <FILE>          write the iteration counts as a 16-bit greyscale PGM image
+    pfm <FILE>          write the iteration counts as a floating-point PFM image
+
+OPTIONS:
+    -w, --width <N>         number of columns (default: the terminal's width, or 230 when writing
+                            a file). For halfblock and braille, the number of character cells,
+                            which hold several points each. For image, the width in pixels
+    -H, --height <N>        number of rows (default: the terminal's height minus one, or 66),
+                            or character cells or pixels as for --width
+    -i, --iterations <N>    maximum number of iterations per pixel (default: 1000)
+        --real-min <X>      left edge of the viewport (default: -2.0)
+        --real-max <X>      right edge of the viewport (default: 1.0)
+        --imag-min <Y>      top edge of the viewport (default: -1.0)
+        --imag-max <Y>      bottom edge of the viewport (default: 1.0)
+        --center <X,Y>      center of the viewport, instead of the min/max options
+        --zoom <Z>          magnification around --center (default: 1.0). Zoom 1 fits the
+                            whole set, without stretching it to the shape of the output
+        --formula <NAME>    the equation to iterate: mandelbrot (z^2 + c), multibrot (z^d + c),
+                            burning-ship, tricorn, celtic, buffalo, perpendicular
+                            (default: mandelbrot). Each comes with its own view and bailout.
+                            Or a formula of your own for the next z, using z, c, pixel, n (the
+                            step), i, pi, e, + - * / ^ and exp, log, sqrt, sin, cos, tan, sinh,
+                            cosh, tanh, conj, abs, re, im and pow(a,b), e.g. 'z^3 - z + c'
+        --power <D>         the power d of the multibrot formula, any number greater than 1
+                            (default: 3). Implies --formula multibrot
+        --julia <X,Y>       draw the Julia set of the point X+Yi instead of the Mandelbrot set:
+                            the pixel is where z starts, and c is this point. Any point of the
+                            Mandelbrot set can be given, e.g. --julia -0.123,0.745. The view is
+                            centered on 0 unless --center is given
+        --newton            draw the basins of Newton's method for z^3 - 1 instead: each point is
+                            coloured by the root it leads to, darker the longer it takes
+        --roots <LIST>      a Newton fractal for the polynomial with these roots, separated by
+                            spaces, each as X,Y or X, e.g. --roots '1 -1 0,1 0,-1'
+        --polynomial <LIST> a Newton fractal for the polynomial with these coefficients, highest
+                            power first, e.g. --polynomial '1 0 -2 2' for z^3 - 2z + 2
+        --relaxation <A>    how far along each Newton step to go, as X or X,Y (default: 1.0)
+        --nova              add c to each Newton step, where c is the pixel, and start from the
+                            first root, for a Nova fractal. With --julia, c is that point
+        --buddhabrot        draw where the orbits of escaping points go, instead of how long they
+                            take, by tracing the orbits of random points
+        --nebulabrot <R,G,B>
+                            a Buddhabrot with its own iterations for the red, green and blue
+                            channels, e.g. --nebulabrot 5000,500,50
+        --anti-buddhabrot   trace the orbits that never escape instead
+        --samples <N>       the number of random points to trace for a Buddhabrot
+                            (default: 50 per pixel)
+        --seed <N>          where the random points of a Buddhabrot start (default: 1)
+        --bookmark <NAME>   start at a bookmark, instead of --center/--zoom or the bounds
+        --preset <NAME>     start at a famous place from the built-in catalogue, like seahorse or
+                            elephant. The list command describes them all
+        --bookmarks <FILE>  where bookmarks are kept (default: ~/.mandelbrot-bookmarks).
+                            The bookmarks command lists them
+        --scene <FILE>      start from the settings in a scene file, or in a PNG written by this
+                            program, then apply the other options
+        --save-scene <FILE> write the settings of this render to a scene file
+        --write-scene       for modes that write a file: save the scene next to it, as a .toml
+                            file with the same name
+        --cell-aspect <R>   height of a character cell divided by its width, so the set is not
+                            stretched (default: measured by the terminal, or 2.0)
+        --bailout <R>       radius z has to pass to count as escaped (default: 2.0, or more for
+                            multibrot powers below 2)
+        --norm <NAME>       how the size of z is measured against the bailout radius:
+                            euclidean, manhattan, chebyshev, real, imaginary (default: euclidean)
+    -j, --threads <N>       number of threads to render with (default: one per CPU core)
+        --ramp <CHARS>      characters for ascii output, from quick to slow escapes, with the last
+                            one for the inside of the set (default: \"¸.•›-˛˙˛‘¨¸ \")
+        --scale <NAME>      how iterations are spread over the ramp: linear, log, histogram
+                            (default: log)
+        --threshold <T>     for braille: how far up the --scale ramp, from 0.0 to 1.0, a point has
+                            to be for its dot to be drawn (default: 1.0, only the set itself)
+        --palette <NAME>    colours for ansi and image output: grayscale, fire, ocean, rainbow
+                            (default: ocean)
+        --colors <DEPTH>    colours the terminal can show: truecolor, 256, 16
+                            (default: detected from COLORTERM and TERM)
+        --protocol <NAME>   graphics protocol for image output: sixel, kitty
+                            (default: kitty in terminals known to support it, otherwise sixel)
+        --smooth            use the fractional iteration count, for colour gradients without bands
+        --periods           colour the inside of the set by the period of each point's orbit
+        --no-periodicity    iterate points whose orbit is caught in a cycle all the way, instead
+                            of stopping once the cycle is found
+        --periodicity-tolerance <T>
+                            how close an orbit must come back to count as a cycle (default: 1e-12)
+        --no-interior-shortcut
+                            iterate points in the main cardioid and period-2 bulb too, instead of
+                            skipping them (slower, for benchmarking; the iteration counts are
+                            the same, but --periods may differ close to their edges)
+    -h, --help              print this help
+";
+
+// the number of random points traced for each pixel of a Buddhabrot, unless --samples is given.
+const SAMPLES_PER_PIXEL: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Ascii,
+    Ansi,
+    HalfBlock,
+    Braille,
+    Image,
+    Explore,
+    Raw,
+    Png(PathBuf),
+    Ppm(PathBuf),
+    Pgm(PathBuf),
+    Pfm(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub mode: Mode,
+    pub config: Config,
+    // where the view is, for the explorer to move around from. Config's view is made from it.
+    pub location: Location,
+    pub palette: Palette,
+    pub threads: usize,
+    pub smooth: bool,
+    pub periods: bool,
+    // the Newton fractal to draw instead of the formula in config, if any.
+    pub newton: Option<Newton>,
+    // the orbits to trace for a Buddhabrot, if any.
+    pub buddhabrot: Option<Buddhabrot>,
+    pub ramp: Ramp,
+    pub scale: Scale,
+    pub color_depth: ColorDepth,
+    pub protocol: Protocol,
+    pub threshold: f64,
+    pub cell_aspect: f64,
+    pub bookmarks: PathBuf,
+    // where to write the scene of this render, if anywhere.
+    pub save_scene: Option<PathBuf>,
+}
+
+impl Mode {
+    // the name the mode is selected with on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Ascii => "ascii",
+            Mode::Ansi => "ansi",
+            Mode::HalfBlock => "halfblock",
+            Mode::Braille => "braille",
+            Mode::Image => "image",
+            Mode::Explore => "explore",
+            Mode::Raw => "raw",
+            Mode::Png(_) => "png",
+            Mode::Ppm(_) => "ppm",
+            Mode::Pgm(_) => "pgm",
+            Mode::Pfm(_) => "pfm",
+        }
+    }
+
+    // the file the mode writes, if it writes one.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Mode::Png(path) | Mode::Ppm(path) | Mode::Pgm(path) | Mode::Pfm(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl Args {
+    // everything needed to make this render again.
+    pub fn scene(&self) -> Scene {
+        Scene {
+            config: self.config.clone(),
+            newton: self.newton.clone(),
+            buddhabrot: self.buddhabrot.clone(),
+            coloring: Coloring {
+                palette: self.palette,
+                scale: self.scale,
+                smooth: self.smooth,
+                periods: self.periods,
+                ramp: self.ramp.clone(),
+                threshold: self.threshold,
+            },
+            output: Output {
+                mode: self.mode.name().to_string(),
+                path: self.mode.path().map(Path::to_path_buf),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Render(Box<Args>),
+    // print the bookmarks in the file.
+    ListBookmarks(PathBuf),
+    // describe the built-in presets.
+    ListPresets,
+    Help,
+}
+
+/*
+Turns the raw arguments (without the program name) into a Command.
+Returns a human readable message if an argument is unknown, missing its value or out of range.
+*/
+pub fn parse_args<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args: Vec<String> = args.into_iter().collect();
+    // the scene file is read first, since every other option starts from its values.
+    let scene = match find_option(&args, "--scene") {
+        Some(path) => Some(Scene::load(Path::new(&path))?),
+        None => None,
+    };
+    let base = scene.clone().unwrap_or_default();
+    let defaults = &base.config;
+
+    let mut positional = Vec::new();
+    // the iterations and palette are optional, since they may also come from a bookmark.
+    let mut palette = None;
+    let mut threads = parallel::default_threads();
+    let mut smooth = base.coloring.smooth;
+    // whether --smooth or --periods was given, rather than taken from a scene.
+    let mut coloring_flag = false;
+    // the bailout starts from the formula's, which is only known once all options are read.
+    let mut bailout_radius = None;
+    let mut norm = None;
+    let mut interior_shortcut = defaults.interior_shortcut;
+    let mut periods = base.coloring.periods;
+    let mut ramp = base.coloring.ramp.clone();
+    let mut scale = base.coloring.scale;
+    let mut color_depth = None;
+    let mut protocol = None;
+    let mut threshold = base.coloring.threshold;
+    let mut periodicity_check = defaults.periodicity_check;
+    let mut periodicity_tolerance = defaults.periodicity_tolerance;
+    let mut save_scene = None;
+    let mut write_scene = false;
+    let mut cell_aspect = None;
+    // when not given, the width and height are taken from the terminal where that makes sense.
+    let mut width = None;
+    let mut height = None;
+    let mut max_iterations = None;
+    let mut bookmark = None;
+    let mut preset = None;
+    let mut bookmarks = None;
+
+    // the bounds are optional, since they may also be derived from --center and --zoom.
+    let mut real_min = None;
+    let mut real_max = None;
+    let mut imaginary_min = None;
+    let mut imaginary_max = None;
+    let mut center = None;
+    let mut zoom = None;
+    // the constant of the Julia set to draw instead of the Mandelbrot set.
+    let mut julia = None;
+    let mut formula = None;
+    let mut power = None;
+    // a Newton fractal: the polynomial from --roots or --polynomial, and its other settings.
+    let mut newton_flag = false;
+    let mut polynomial = None;
+    let mut relaxation = None;
+    let mut nova = false;
+    // a Buddhabrot, and how to sample it.
+    let mut buddhabrot_flag = false;
+    let mut nebula = None;
+    let mut anti = false;
+    let mut samples = None;
+    let mut seed = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(Command::Help);
+        }
+
+        if !arg.starts_with('-') {
+            positional.push(arg);
+            continue;
+        }
+
+        // flags take no value, so they are handled before looking for one.
+        match arg.as_str() {
+            "--smooth" => {
+                smooth = true;
+                coloring_flag = true;
+                continue;
+            }
+            "--periods" => {
+                periods = true;
+                coloring_flag = true;
+                continue;
+            }
+            "--no-periodicity" => {
+                periodicity_check = false;
+                continue;
+            }
+            "--no-interior-shortcut" => {
+                interior_shortcut = false;
+                continue;
+            }
+            "--write-scene" => {
+                write_scene = true;
+                continue;
+            }
+            "--newton" => {
+                newton_flag = true;
+                continue;
+            }
+            "--nova" => {
+                nova = true;
+                continue;
+            }
+            "--buddhabrot" => {
+                buddhabrot_flag = true;
+                continue;
+            }
+            "--anti-buddhabrot" => {
+                anti = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        // split "--width=100" into the option name and its inline value.
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(format!("missing value for '{}'", name)),
+        };
+
+        match name.as_str() {
+            "-w" | "--width" => width = Some(parse_value(&name, &value)?),
+            "-H" | "--height" => height = Some(parse_value(&name, &value)?),
+            "--cell-aspect" => cell_aspect = Some(parse_value(&name, &value)?),
+            "-i" | "--iterations" => max_iterations = Some(parse_value(&name, &value)?),
+            "--bookmark" => bookmark = Some(value),
+            "--preset" => {
+                preset = Some(Preset::find(&value).ok_or_else(|| {
+                    format!("unknown preset '{}' (the list command shows them)", value)
+                })?)
+            }
+            "--bookmarks" => bookmarks = Some(PathBuf::from(value)),
+            // already read before the loop.
+            "--scene" => {}
+            "--save-scene" => save_scene = Some(PathBuf::from(value)),
+            "--real-min" => real_min = Some(parse_value(&name, &value)?),
+            "--real-max" => real_max = Some(parse_value(&name, &value)?),
+            "--imag-min" => imaginary_min = Some(parse_value(&name, &value)?),
+            "--imag-max" => imaginary_max = Some(parse_value(&name, &value)?),
+            "--center" => center = Some(parse_point(&name, &value)?),
+            "--formula" => formula = Some(Fractal::parse(&value)?),
+            "--power" => power = Some(parse_value(&name, &value)?),
+            "--julia" => {
+                let (re, im) = parse_point(&name, &value)?;
+                julia = Some(Complex::new(re, im));
+            }
+            "--roots" | "--polynomial" => {
+                if polynomial.is_some() {
+                    return Err("use either --roots or --polynomial, not both".to_string());
+                }
+                let numbers = parse_numbers(&name, &value)?;
+                polynomial = Some(match name.as_str() {
+                    "--roots" => Newton::from_roots(&numbers)?,
+                    _ => Newton::from_coefficients(&numbers)?,
+                });
+            }
+            "--relaxation" => match parse_numbers(&name, &value)?[..] {
+                [factor] => relaxation = Some(factor),
+                _ => return Err(format!("expected one number for '{}'", name)),
+            },
+            "--nebulabrot" => {
+                let limits: Vec<usize> = value
+                    .split(',')
+                    .map(|limit| parse_value(&name, limit))
+                    .collect::<Result<_, _>>()?;
+                nebula = Some(<[usize; 3]>::try_from(limits).map_err(|_| {
+                    format!(
+                        "expected '<red>,<green>,<blue>' for '{}', got '{}'",
+                        name, value
+                    )
+                })?);
+            }
+            "--samples" => samples = Some(parse_value(&name, &value)?),
+            "--seed" => seed = Some(parse_value::<usize>(&name, &value)? as u64),
+            "--zoom" => zoom = Some(parse_value(&name, &value)?),
+            "--bailout" => bailout_radius = Some(parse_value(&name, &value)?),
+            "--norm" => {
+                norm = Some(
+                    Norm::from_name(&value).ok_or_else(|| format!("unknown norm '{}'", value))?,
+                )
+            }
+            "--periodicity-tolerance" => periodicity_tolerance = parse_value(&name, &value)?,
+            "-j" | "--threads" => threads = parse_value(&name, &value)?,
+            "--ramp" => ramp = Ramp::new(&value)?,
+            "--scale" => {
+                scale =
+                    Scale::from_name(&value).ok_or_else(|| format!("unknown scale '{}'", value))?
+            }
+            "--threshold" => threshold = parse_value(&name, &value)?,
+            "--colors" => {
+                color_depth = Some(
+                    ColorDepth::from_name(&value)
+                        .ok_or_else(|| format!("unknown colour depth '{}'", value))?,
+                )
+            }
+            "--protocol" => {
+                protocol = Some(
+                    Protocol::from_name(&value)
+                        .ok_or_else(|| format!("unknown graphics protocol '{}'", value))?,
+                )
+            }
+            "--palette" => {
+                palette = Some(
+                    Palette::from_name(&value)
+                        .ok_or_else(|| format!("unknown palette '{}'", value))?,
+                )
+            }
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+    }
+
+    let bookmarks = bookmarks.unwrap_or_else(Bookmarks::default_path);
+    if positional.first().map(String::as_str) == Some("bookmarks") {
+        if positional.len() > 1 {
+            return Err("too many arguments for 'bookmarks'".to_string());
+        }
+        return Ok(Command::ListBookmarks(bookmarks));
+    }
+    if positional.first().map(String::as_str) == Some("list") {
+        if positional.len() > 1 {
+            return Err("too many arguments for 'list'".to_string());
+        }
+        return Ok(Command::ListPresets);
+    }
+    // without a mode on the command line, the scene's mode is used.
+    if let (true, Some(scene)) = (positional.is_empty(), &scene) {
+        positional.push(scene.output.mode.clone());
+        if let Some(path) = &scene.output.path {
+            positional.push(path.to_string_lossy().into_owned());
+        }
+    }
+    let mode = parse_mode(&positional)?;
+
+    /*
+    The size of the grid, and the shape of one point on screen, which together decide how much
+    of the plane fits in the view without stretching it. The terminal is only asked for its size
+    if it is needed: for output to the terminal, when -w or -H is missing, or for the cell shape.
+    */
+    let output_to_terminal = matches!(
+        mode,
+        Mode::Ascii | Mode::Ansi | Mode::HalfBlock | Mode::Braille | Mode::Image | Mode::Explore
+    );
+    let terminal = if output_to_terminal {
+        TerminalSize::detect()
+    } else {
+        None
+    };
+    let cell_aspect: f64 = cell_aspect
+        .unwrap_or_else(|| terminal.map_or(DEFAULT_CELL_ASPECT, |terminal| terminal.cell_aspect()));
+    if cell_aspect <= 0.0 {
+        return Err("cell aspect must be greater than 0".to_string());
+    }
+
+    // the sub-cell modes fit more than one point in each character cell.
+    let (points_per_column, points_per_row) = match mode {
+        Mode::HalfBlock => (1, 2),
+        Mode::Braille => (2, 4),
+        _ => (1, 1),
+    };
+    let (detected_width, detected_height) = match (&mode, terminal) {
+        (Mode::Image, Some(terminal)) => image_size(terminal),
+        // one row is left for the shell prompt that follows the output.
+        (_, Some(terminal)) if output_to_terminal => {
+            (terminal.columns, terminal.rows.saturating_sub(1).max(1))
+        }
+        _ => (defaults.view.width, defaults.view.height),
+    };
+    // a scene holds the number of points, which is already multiplied out for the sub-cell modes.
+    let (width, height) = match &scene {
+        Some(scene) => (
+            width.map_or(scene.config.view.width, |width| width * points_per_column),
+            height.map_or(scene.config.view.height, |height| height * points_per_row),
+        ),
+        None => (
+            width.unwrap_or(detected_width) * points_per_column,
+            height.unwrap_or(detected_height) * points_per_row,
+        ),
+    };
+    let point_aspect = match mode {
+        Mode::Ascii | Mode::Ansi | Mode::HalfBlock | Mode::Braille | Mode::Explore => {
+            cell_aspect * points_per_column as f64 / points_per_row as f64
+        }
+        // image pixels are square.
+        _ => 1.0,
+    };
+    if smooth && periods {
+        return Err("--smooth and --periods cannot be used together".to_string());
+    }
+    if coloring_flag && mode == Mode::Explore {
+        return Err("the explorer does not draw --smooth or --periods".to_string());
+    }
+
+    /*
+    The Newton fractal, if there is one: any of its options asks for one, starting from the
+    scene's when it has one, or else z^3 - 1. A new polynomial keeps the scene's other settings.
+    A formula given on its own switches back to the escape-time fractals, as a Buddhabrot does.
+    */
+    let wants_newton = newton_flag || polynomial.is_some() || relaxation.is_some() || nova;
+    let wants_buddhabrot =
+        buddhabrot_flag || nebula.is_some() || anti || samples.is_some() || seed.is_some();
+    if wants_newton && wants_buddhabrot {
+        return Err("use either a Newton fractal or a Buddhabrot, not both".to_string());
+    }
+    if wants_newton && (formula.is_some() || power.is_some()) {
+        return Err("--formula and --power do not go with a Newton fractal".to_string());
+    }
+    let newton = match (polynomial, base.newton.clone()) {
+        (Some(mut polynomial), Some(old)) => {
+            polynomial.relaxation = old.relaxation;
+            polynomial.nova = old.nova;
+            polynomial.tolerance = old.tolerance;
+            Some(polynomial)
+        }
+        (Some(polynomial), None) => Some(polynomial),
+        (None, old) if wants_newton => Some(old.unwrap_or_default()),
+        (None, _) if formula.is_some() || power.is_some() || wants_buddhabrot => None,
+        (None, old) => old,
+    };
+    let newton = match newton {
+        Some(mut newton) => {
+            newton.nova |= nova;
+            newton.relaxation = relaxation.unwrap_or(newton.relaxation);
+            newton.validate()?;
+            if smooth || periods {
+                return Err("--smooth and --periods do not go with a Newton fractal".to_string());
+            }
+            if mode == Mode::Explore {
+                return Err("the explorer does not draw Newton fractals".to_string());
+            }
+            Some(newton)
+        }
+        None => None,
+    };
+
+    // the Buddhabrot, if there is one: any of its options asks for one, starting from the
+    // scene's. A Newton fractal, or a formula given on its own, switches back.
+    let buddhabrot = match base.buddhabrot.clone() {
+        _ if newton.is_some() => None,
+        None if !wants_buddhabrot => None,
+        Some(_) if !wants_buddhabrot && (formula.is_some() || power.is_some()) => None,
+        old => {
+            let mut buddhabrot = old.unwrap_or_else(|| Buddhabrot {
+                samples: width * height * SAMPLES_PER_PIXEL,
+                ..Buddhabrot::default()
+            });
+            buddhabrot.nebula = nebula.or(buddhabrot.nebula);
+            buddhabrot.anti |= anti;
+            buddhabrot.samples = samples.unwrap_or(buddhabrot.samples);
+            buddhabrot.seed = seed.unwrap_or(buddhabrot.seed);
+            buddhabrot.validate()?;
+            if smooth || periods {
+                return Err("--smooth and --periods do not go with a Buddhabrot".to_string());
+            }
+            if mode == Mode::Explore {
+                return Err("the explorer does not draw Buddhabrots".to_string());
+            }
+            Some(buddhabrot)
+        }
+    };
+    // other roots, or a switch between Newton and Nova, make a new picture, as a new formula does.
+    let new_newton = newton.as_ref().is_some_and(|newton| {
+        base.newton
+            .as_ref()
+            .is_none_or(|old| old.roots() != newton.roots() || old.nova != newton.nova)
+    });
+    if threads == 0 {
+        return Err("threads must be at least 1".to_string());
+    }
+
+    let explicit_min_max = real_min.is_some()
+        || real_max.is_some()
+        || imaginary_min.is_some()
+        || imaginary_max.is_some();
+    let uses_center_zoom = center.is_some() || zoom.is_some();
+    if explicit_min_max && uses_center_zoom {
+        return Err("use either --center/--zoom or the --real/--imag bounds, not both".to_string());
+    }
+    if bookmark.is_some() && preset.is_some() {
+        return Err("use either --bookmark or --preset, not both".to_string());
+    }
+    let named = bookmark.is_some() || preset.is_some();
+    if named && (explicit_min_max || uses_center_zoom) {
+        return Err(
+            "--bookmark and --preset cannot be combined with --center/--zoom or the bounds"
+                .to_string(),
+        );
+    }
+    // --power picks the Multibrot set, and only goes with it.
+    let formula = match (formula, power) {
+        (None | Some(Fractal::Multibrot(_)), Some(power)) => Some(Fractal::with_power(power)?),
+        (Some(_), Some(_)) => return Err("--power only goes with --formula multibrot".to_string()),
+        (formula, None) => formula,
+    };
+    // a formula or Julia set other than the scene's (or any, without a scene) is a new picture.
+    // It starts out at the default view of the formula, or for a Julia set, centered on zero.
+    let new_formula = formula
+        .as_ref()
+        .is_some_and(|formula| *formula != defaults.formula);
+    let new_julia = julia.is_some() && julia != defaults.julia;
+    // a scene's exact bounds are kept, unless the view is placed some other way.
+    let uses_min_max = explicit_min_max
+        || (scene.is_some()
+            && !uses_center_zoom
+            && !named
+            && !new_julia
+            && !new_formula
+            && !new_newton);
+
+    // where to start: a bookmark or preset, or the center and zoom, which default to the whole set.
+    let location = match (&bookmark, preset) {
+        (Some(name), _) => Bookmarks::load(&bookmarks)?
+            .get(name)
+            .ok_or_else(|| format!("no bookmark named '{}' in {}", name, bookmarks.display()))?
+            .clone(),
+        (None, Some(preset)) => preset.location.clone(),
+        (None, None) => {
+            // a scene's view is the starting point, so that --zoom alone zooms further into it.
+            // Without a scene, that is the whole set, as in Location::default.
+            // A new formula or Newton fractal starts from all of its own fractal instead.
+            let (whole, formula) = match (&newton, &formula) {
+                (Some(newton), _) if new_newton => (
+                    Location::fitting(
+                        &newton.default_view(),
+                        defaults.max_iterations,
+                        base.coloring.palette,
+                    ),
+                    defaults.formula.clone(),
+                ),
+                (_, Some(formula)) if new_formula => (
+                    Location::fitting(
+                        &formula.default_view(),
+                        defaults.max_iterations,
+                        base.coloring.palette,
+                    ),
+                    formula.clone(),
+                ),
+                _ => (
+                    Location::from_view(
+                        &defaults.view,
+                        defaults.max_iterations,
+                        base.coloring.palette,
+                    ),
+                    defaults.formula.clone(),
+                ),
+            };
+            let mut location = Location {
+                julia: defaults.julia,
+                formula,
+                ..whole
+            };
+            if new_julia {
+                location.center = Complex::new(0.0, 0.0);
+                location.zoom = 1.0;
+            }
+            if let Some((center_x, center_y)) = center {
+                location.center = Complex::new(center_x, center_y);
+            }
+            if let Some(zoom) = zoom {
+                location.zoom = zoom;
+            }
+            if location.zoom <= 0.0 {
+                return Err("zoom must be greater than 0".to_string());
+            }
+            location
+        }
+    };
+    let max_iterations = max_iterations.unwrap_or(location.max_iterations);
+    let palette = palette.unwrap_or(location.palette);
+    let julia = julia.or(location.julia);
+    let formula = formula.unwrap_or_else(|| location.formula.clone());
+
+    // the bailout of the scene, unless the formula changed, as it may need another radius.
+    let mut bailout = if formula == defaults.formula {
+        defaults.bailout
+    } else {
+        formula.default_bailout()
+    };
+    bailout.radius = bailout_radius.unwrap_or(bailout.radius);
+    bailout.norm = norm.unwrap_or(bailout.norm);
+
+    // without any bounds, the whole set is shown as if --center and --zoom had been given.
+    let view = if !uses_min_max {
+        location.view(width, height, point_aspect)
+    } else {
+        View::new(
+            real_min.unwrap_or(defaults.view.real_min),
+            real_max.unwrap_or(defaults.view.real_max),
+            imaginary_min.unwrap_or(defaults.view.imaginary_min),
+            imaginary_max.unwrap_or(defaults.view.imaginary_max),
+            width,
+            height,
+        )
+    };
+
+    let mut config = Config::new(view, max_iterations);
+    config.bailout = bailout;
+    config.interior_shortcut = interior_shortcut;
+    config.periodicity_check = periodicity_check;
+    config.periodicity_tolerance = periodicity_tolerance;
+    config.julia = julia;
+    config.formula = formula.clone();
+    config.validate()?;
+    if newton.as_ref().is_some_and(|newton| !newton.nova) && julia.is_some() {
+        return Err("--julia only goes with --nova for a Newton fractal".to_string());
+    }
+    if buddhabrot.is_some() && julia.is_some() {
+        return Err("a Buddhabrot has no Julia sets".to_string());
+    }
+
+    let save_scene = match (write_scene, mode.path()) {
+        (false, _) => save_scene,
+        (true, _) if save_scene.is_some() => {
+            return Err("use either --save-scene or --write-scene, not both".to_string())
+        }
+        (true, Some(path)) => Some(Scene::path_next_to(path)),
+        (true, None) => return Err("--write-scene needs a mode that writes a file".to_string()),
+    };
+
+    // bounds given as min/max are turned into a location, which keeps their center and zoom.
+    let location = if uses_min_max {
+        Location {
+            julia,
+            formula,
+            ..Location::from_view(&view, max_iterations, palette)
+        }
+    } else {
+        Location {
+            max_iterations,
+            palette,
+            julia,
+            formula,
+            ..location
+        }
+    };
+
+    Ok(Command::Render(Box::new(Args {
+        mode,
+        config,
+        location,
+        palette,
+        threads,
+        smooth,
+        periods,
+        newton,
+        buddhabrot,
+        ramp,
+        scale,
+        color_depth: color_depth.unwrap_or_else(ColorDepth::detect),
+        protocol: protocol.unwrap_or_else(Protocol::detect),
+        threshold,
+        cell_aspect,
+        bookmarks,
+        save_scene,
+    })))
+}
+
+/*
+The size in pixels for image output, filling the terminal's text area minus the last row.
+Terminals that do not report their size in pixels get a guess of 8x16 pixels per cell.
+*/
+fn image_size(terminal: TerminalSize) -> (usize, usize) {
+    let rows = terminal.rows.saturating_sub(1).max(1);
+    if terminal.pixel_width == 0 || terminal.pixel_height == 0 {
+        return (terminal.columns * 8, rows * 16);
+    }
+    (
+        terminal.pixel_width,
+        terminal.pixel_height * rows / terminal.rows,
+    )
+}
+
+// looks for an option's value ahead of the main loop, in either the "--opt value" or "--opt=value" form.
+fn find_option(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(index, arg)| {
+        if arg == name {
+            args.get(index + 1).cloned()
+        } else {
+            arg.strip_prefix(name)?
+                .strip_prefix('=')
+                .map(str::to_string)
+        }
+    })
+}
+
+// the first positional argument is the mode, the rest are the arguments of that mode.
+fn parse_mode(positional: &[String]) -> Result<Mode, String> {
+    let (name, rest) = match positional.split_first() {
+        Some((name, rest)) => (name.as_str(), rest),
+        None => return Ok(Mode::Ascii),
+    };
+
+    // modes that write a file take exactly one path, the others take nothing.
+    let file_mode: Option<fn(PathBuf) -> Mode> = match name {
+        "ascii" | "ansi" | "halfblock" | "braille" | "image" | "explore" | "raw" => None,
+        "png" => Some(Mode::Png),
+        "ppm" => Some(Mode::Ppm),
+        "pgm" => Some(Mode::Pgm),
+        "pfm" => Some(Mode::Pfm),
+        _ => return Err(format!("unknown mode '{}'", name)),
+    };
+
+    let mode = match (file_mode, rest) {
+        (None, []) => match name {
+            "ascii" => Mode::Ascii,
+            "ansi" => Mode::Ansi,
+            "halfblock" => Mode::HalfBlock,
+            "braille" => Mode::Braille,
+            "image" => Mode::Image,
+            "explore" => Mode::Explore,
+            _ => Mode::Raw,
+        },
+        (Some(mode), [path]) => mode(PathBuf::from(path)),
+        (Some(_), []) => return Err(format!("{} needs the path of the file to write", name)),
+        _ => return Err(format!("too many arguments for '{}'", name)),
+    };
+    Ok(mode)
+}
+
+/*
+Parses a single number. Floats are also checked to be finite,
+since "inf" and "NaN" are accepted by f64's parser but make no sense as coordinates.
+*/
+fn parse_value<T: ParseValue>(name: &str, value: &str) -> Result<T, String> {
+    T::parse_value(value.trim()).ok_or_else(|| format!("invalid value '{}' for '{}'", value, name))
+}
+
+// parses "x,y" into a point on the complex plane.
+fn parse_point(name: &str, value: &str) -> Result<(f64, f64), String> {
+    match value.split_once(',') {
+        Some((x, y)) => Ok((parse_value(name, x)?, parse_value(name, y)?)),
+        None => Err(format!(
+            "expected '<x>,<y>' for '{}', got '{}'",
+            name, value
+        )),
+    }
+}
+
+// parses a list of complex numbers, as for --roots.
+fn parse_numbers(name: &str, value: &str) -> Result<Vec<Complex<f64>>, String> {
+    newton::parse_numbers(value).map_err(|error| format!("{} for '{}'", error, name))
+}
+
+trait ParseValue: Sized {
+    fn parse_value(value: &str) -> Option<Self>;
+}
+
+impl ParseValue for usize {
+    fn parse_value(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+}
+
+impl ParseValue for f64 {
+    fn parse_value(value: &str) -> Option<Self> {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // splits at spaces, as a shell would, except inside single quotes.
+    fn parse(line: &str) -> Result<Command, String> {
+        let words = line
+            .split('\'')
+            .enumerate()
+            .flat_map(|(index, part)| match index % 2 {
+                0 => part.split_whitespace().map(str::to_string).collect(),
+                _ => vec![part.to_string()],
+            });
+        parse_args(words)
+    }
+
+    fn render(line: &str) -> Args {
+        match parse(line) {
+            Ok(Command::Render(args)) => *args,
+            other => panic!("'{}' gave {:?}", line, other),
+        }
+    }
+
+    // checks that every line fails, with an error message that contains the expected part.
+    fn assert_errors(cases: &[(&str, &str)]) {
+        for &(line, message) in cases {
+            let error = match parse(line) {
+                Err(error) => error,
+                Ok(command) => panic!("'{}' should fail, gave {:?}", line, command),
+            };
+            assert!(
+                error.contains(message),
+                "'{}' gave '{}', expected '{}'",
+                line,
+                error,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse("-h"), Ok(Command::Help));
+        assert_eq!(parse("raw -w 5 --help"), Ok(Command::Help));
+        // help wins over anything wrong after it.
+        assert_eq!(parse("--help --width x"), Ok(Command::Help));
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(render("raw").mode, Mode::Raw);
+        assert_eq!(render("ascii -w 20 -H 10").mode, Mode::Ascii);
+        assert_eq!(render("-w 20 -H 10").mode, Mode::Ascii);
+    }
+
+    #[test]
+    fn sizes() {
+        let args = render("raw -w 30 -H 20 -i 300");
+        assert_eq!((args.config.view.width, args.config.view.height), (30, 20));
+        assert_eq!(args.config.max_iterations, 300);
+        // inline values.
+        let args = render("raw --width=40 --height=10 --iterations=50");
+        assert_eq!((args.config.view.width, args.config.view.height), (40, 10));
+        assert_eq!(args.config.max_iterations, 50);
+    }
+
+    #[test]
+    fn views() {
+        let args = render("raw --real-min -1 --real-max 0.5 --imag-min -0.25 --imag-max 0.75");
+        let view = &args.config.view;
+        assert_eq!(
+            (
+                view.real_min,
+                view.real_max,
+                view.imaginary_min,
+                view.imaginary_max
+            ),
+            (-1.0, 0.5, -0.25, 0.75)
+        );
+
+        let args = render("raw --center -0.75,0.1 --zoom 20");
+        assert_eq!(args.location.center, Complex::new(-0.75, 0.1));
+        assert_eq!(args.location.zoom, 20.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert_errors(&[
+            ("raw --width", "missing value for '--width'"),
+            ("raw --unknown 1", "unknown option '--unknown'"),
+            ("raw -w many", "invalid value 'many' for '-w'"),
+            ("raw --real-min inf", "invalid value 'inf' for '--real-min'"),
+            ("raw --center 1", "expected '<x>,<y>' for '--center'"),
+            ("raw --center 1,x", "invalid value 'x' for '--center'"),
+            ("movie", "unknown mode 'movie'"),
+            ("raw -w 0", "width and height must be at least 1"),
+            ("raw -H 0", "width and height must be at least 1"),
+            ("raw -i 0", "iterations must be at least 1"),
+            ("raw --center 0,0 --real-min -1", "not both"),
+            ("raw --zoom 2 --imag-max 1", "not both"),
+            ("raw --zoom 0", "zoom must be greater than 0"),
+            ("raw --real-min 1 --real-max 0", "the viewport is empty"),
+        ]);
+    }
+
+    #[test]
+    fn image_files() {
+        assert_eq!(render("png a.png").mode, Mode::Png(PathBuf::from("a.png")));
+        assert_eq!(render("raw --palette fire").palette, Palette::Fire);
+        assert_errors(&[
+            ("png", "png needs the path of the file to write"),
+            ("png a.png b.png", "too many arguments for 'png'"),
+            ("raw extra", "too many arguments for 'raw'"),
+            ("raw --palette mud", "unknown palette 'mud'"),
+        ]);
+    }
+
+    #[test]
+    fn netpbm_files() {
+        for (line, mode) in [
+            ("ppm a.ppm", Mode::Ppm(PathBuf::from("a.ppm"))),
+            ("pgm a.pgm", Mode::Pgm(PathBuf::from("a.pgm"))),
+            ("pfm a.pfm", Mode::Pfm(PathBuf::from("a.pfm"))),
+        ] {
+            assert_eq!(render(line).mode, mode);
+        }
+        assert_errors(&[("pfm", "pfm needs the path of the file to write")]);
+    }
+
+    #[test]
+    fn threads() {
+        assert_eq!(render("raw -j 3").threads, 3);
+        assert_eq!(render("raw --threads=2").threads, 2);
+        assert_errors(&[
+            ("raw -j -1", "invalid value '-1' for '-j'"),
+            ("raw -j 0", "threads must be at least 1"),
+        ]);
+    }
+
+    #[test]
+    fn smooth() {
+        assert!(render("raw --smooth").smooth);
+        assert!(!render("raw").smooth);
+    }
+
+    #[test]
+    fn bailout() {
+        let args = render("raw --bailout 100 --norm manhattan");
+        assert_eq!(args.config.bailout.radius, 100.0);
+        assert_eq!(args.config.bailout.norm, Norm::Manhattan);
+        assert_errors(&[
+            ("raw --norm round", "unknown norm 'round'"),
+            (
+                "raw --bailout 0",
+                "the bailout radius must be greater than 0",
+            ),
+        ]);
+    }
+
+    #[test]
+    fn interior_shortcut() {
+        assert!(render("raw").config.interior_shortcut);
+        assert!(
+            !render("raw --no-interior-shortcut")
+                .config
+                .interior_shortcut
+        );
+    }
+
+    #[test]
+    fn periodicity() {
+        let args = render("raw --no-periodicity --periodicity-tolerance 1e-9");
+        assert!(!args.config.periodicity_check);
+        assert_eq!(args.config.periodicity_tolerance, 1e-9);
+        assert!(render("raw").config.periodicity_check);
+        assert!(render("raw --periods").periods);
+        assert_errors(&[
+            ("raw --smooth --periods", "cannot be used together"),
+            (
+                "raw --periodicity-tolerance -1",
+                "the periodicity tolerance",
+            ),
+        ]);
+    }
+
+    #[test]
+    fn scale_and_ramp() {
+        let args = render("raw --scale linear --ramp ab");
+        assert_eq!(args.scale, Scale::Linear);
+        assert_eq!(args.ramp, Ramp::new("ab").unwrap());
+        assert_eq!(render("raw").scale, Scale::default());
+        assert_errors(&[
+            ("raw --scale odd", "unknown scale 'odd'"),
+            ("raw --ramp a", "at least two characters"),
+        ]);
+    }
+
+    #[test]
+    fn ansi() {
+        assert_eq!(render("ansi -w 20 -H 10").mode, Mode::Ansi);
+        assert_eq!(
+            render("ansi --colors 256").color_depth,
+            ColorDepth::Xterm256
+        );
+        assert_eq!(render("ansi --colors 16").color_depth, ColorDepth::Ansi16);
+        assert_errors(&[("raw --colors 3", "unknown colour depth '3'")]);
+    }
+
+    #[test]
+    fn sub_cell_modes() {
+        // more than one grid point per character cell.
+        let args = render("halfblock -w 30 -H 20");
+        assert_eq!(args.mode, Mode::HalfBlock);
+        assert_eq!((args.config.view.width, args.config.view.height), (30, 40));
+        let args = render("braille --width=30 --height=20 --threshold 0.5");
+        assert_eq!(args.mode, Mode::Braille);
+        assert_eq!((args.config.view.width, args.config.view.height), (60, 80));
+        assert_eq!(args.threshold, 0.5);
+    }
+
+    #[test]
+    fn image() {
+        assert_eq!(render("image -w 20 -H 10").mode, Mode::Image);
+        assert_eq!(render("image --protocol sixel").protocol, Protocol::Sixel);
+        assert_errors(&[("raw --protocol fax", "unknown graphics protocol 'fax'")]);
+    }
+
+    #[test]
+    fn cell_aspect() {
+        assert_eq!(
+            render("ascii -w 20 -H 10 --cell-aspect 2.5").cell_aspect,
+            2.5
+        );
+        assert_errors(&[("raw --cell-aspect 0", "cell aspect must be greater than 0")]);
+    }
+
+    #[test]
+    fn explore() {
+        assert_eq!(render("explore -w 20 -H 10").mode, Mode::Explore);
+    }
+
+    #[test]
+    fn bookmarks() {
+        assert_eq!(
+            parse("bookmarks --bookmarks marks.txt"),
+            Ok(Command::ListBookmarks(PathBuf::from("marks.txt")))
+        );
+
+        let path = std::env::temp_dir().join(format!("cli-bookmarks-{}", std::process::id()));
+        let seahorse: Location = "center=-0.75,0.1 zoom=20 iterations=2000 palette=ocean"
+            .parse()
+            .unwrap();
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.insert("seahorse", seahorse.clone()).unwrap();
+        bookmarks.save(&path).unwrap();
+        let args = render(&format!(
+            "raw --bookmark seahorse --bookmarks {}",
+            path.display()
+        ));
+        assert_eq!(args.location, seahorse);
+        assert_eq!(args.config.max_iterations, 2000);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_errors(&[
+            ("bookmarks extra", "too many arguments for 'bookmarks'"),
+            (
+                "raw --bookmark nothing --bookmarks /nonexistent/marks",
+                "no bookmark named 'nothing'",
+            ),
+            ("raw --bookmark a --zoom 2", "cannot be combined"),
+            // the explorer draws plain counts, and would lose these when moving around.
+            ("explore --smooth", "does not draw --smooth or --periods"),
+            ("explore --periods", "does not draw --smooth or --periods"),
+        ]);
+    }
+
+    #[test]
+    fn scenes() {
+        let directory = std::env::temp_dir().join(format!("cli-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("scene.toml");
+        let mut scene = render("raw -w 40 -H 30 --zoom 5 -i 222").scene();
+        scene.output.mode = "ppm".to_string();
+        scene.output.path = Some(PathBuf::from("out.ppm"));
+        scene.save(&path).unwrap();
+
+        let args = render(&format!("--scene {}", path.display()));
+        assert_eq!(args.mode, Mode::Ppm(PathBuf::from("out.ppm")));
+        assert_eq!(args.config, scene.config);
+        // other options change the scene's values, and --zoom zooms further in.
+        let args = render(&format!("raw --scene={} --zoom 10 -i 50", path.display()));
+        assert_eq!(args.mode, Mode::Raw);
+        assert_eq!(args.config.max_iterations, 50);
+        assert_eq!(args.location.zoom, 10.0);
+
+        // the explorer leaves out the colouring of a scene, where it cannot be switched off.
+        let mut smooth = scene.clone();
+        smooth.coloring.smooth = true;
+        smooth.save(&path).unwrap();
+        let args = render(&format!(
+            "explore --cell-aspect 2 --scene {}",
+            path.display()
+        ));
+        assert_eq!(args.mode, Mode::Explore);
+
+        let args = render(&format!(
+            "ppm a.ppm --write-scene --scene {}",
+            path.display()
+        ));
+        assert_eq!(
+            args.save_scene,
+            Some(Scene::path_next_to(Path::new("a.ppm")))
+        );
+        let args = render("raw --save-scene s.toml");
+        assert_eq!(args.save_scene, Some(PathBuf::from("s.toml")));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_errors(&[
+            ("png a.png --write-scene --save-scene b", "not both"),
+            (
+                "raw --write-scene",
+                "--write-scene needs a mode that writes a file",
+            ),
+            (
+                "raw --scene /nonexistent/scene.toml",
+                "/nonexistent/scene.toml",
+            ),
+        ]);
+    }
+
+    #[test]
+    fn scenes_from_images() {
+        use rusty_mandelbrot::render::png;
+        use rusty_mandelbrot::scene::PNG_KEYWORD;
+
+        let directory = std::env::temp_dir().join(format!("cli-png-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let scene = render("raw -w 3 -H 2 --zoom 5 -i 222").scene();
+        let points = vec![vec![0usize; 3]; 2];
+        let write = |name: &str, text: &[(&str, &str)]| {
+            let mut image = Vec::new();
+            png::write_png_with_text(&points, 222, Palette::default(), text, &mut image).unwrap();
+            let path = directory.join(name);
+            std::fs::write(&path, image).unwrap();
+            path
+        };
+
+        let toml = scene.to_toml();
+        let path = write("scene.png", &[(PNG_KEYWORD, &toml)]);
+        let args = render(&format!("raw --scene {}", path.display()));
+        assert_eq!(args.config, scene.config);
+        // the image is not written over when the scene is rendered again.
+        let args = render(&format!("--scene {}", path.display()));
+        assert_eq!(args.mode, Mode::Ascii);
+
+        let path = write("plain.png", &[]);
+        assert_errors(&[(
+            &format!("raw --scene {}", path.display()),
+            "the image holds no scene",
+        )]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(parse("list"), Ok(Command::ListPresets));
+        let args = render("raw --preset seahorse");
+        assert_eq!(args.location, Preset::find("seahorse").unwrap().location);
+        // iterations and palette given along with a preset win over its own.
+        let args = render("raw --preset seahorse -i 77 --palette fire");
+        assert_eq!(args.config.max_iterations, 77);
+        assert_eq!(args.palette, Palette::Fire);
+        assert_errors(&[
+            ("list extra", "too many arguments for 'list'"),
+            ("raw --preset nowhere", "unknown preset 'nowhere'"),
+            ("raw --bookmark a --preset seahorse", "not both"),
+            ("raw --preset seahorse --zoom 2", "cannot be combined"),
+        ]);
+    }
+
+    #[test]
+    fn julia() {
+        let args = render("raw --julia -0.8,0.156");
+        assert_eq!(args.config.julia, Some(Complex::new(-0.8, 0.156)));
+        // the whole Julia set, around 0.
+        assert_eq!(args.location.center, Complex::new(0.0, 0.0));
+        assert_eq!(args.location.julia, args.config.julia);
+
+        // presets of Julia sets bring their constant along.
+        let args = render("raw --preset rabbit");
+        assert_eq!(args.location, Preset::find("rabbit").unwrap().location);
+        assert!(args.config.julia.is_some());
+        assert_eq!(args.config.julia, args.location.julia);
+        assert_errors(&[("raw --julia 1", "expected '<x>,<y>' for '--julia'")]);
+    }
+
+    #[test]
+    fn formulas() {
+        let args = render("raw --formula burning-ship");
+        assert_eq!(args.config.formula, Fractal::BurningShip);
+        // the view moves to where the Burning Ship is.
+        assert_ne!(args.location, render("raw").location);
+        assert_eq!(
+            render("raw --power 3").config.formula,
+            Fractal::Multibrot(3.0)
+        );
+        assert_eq!(
+            render("raw --formula multibrot --power 4").config.formula,
+            Fractal::Multibrot(4.0)
+        );
+        assert_errors(&[
+            ("raw --formula nothing", "unknown formula 'nothing'"),
+            ("raw --formula tricorn --power 3", "--power only goes with"),
+            ("raw --power 1", "the power must be greater than 1"),
+        ]);
+    }
+
+    #[test]
+    fn typed_formulas() {
+        let args = render("raw --formula 'z^3 - z + c'");
+        assert_eq!(args.config.formula.name(), "z^3 - z + c");
+        assert!(matches!(args.config.formula, Fractal::Custom(_)));
+        assert_errors(&[(
+            "raw --formula 'z^^2'",
+            "invalid formula 'z^^2': unexpected '^'",
+        )]);
+    }
+
+    #[test]
+    fn newton() {
+        let args = render("raw --newton");
+        assert_eq!(args.newton, Some(Newton::default()));
+
+        let args = render("raw --roots '1 -1' --relaxation 0.5 --nova --julia 0.1,0");
+        let newton = args.newton.unwrap();
+        assert_eq!(newton.roots().len(), 2);
+        assert_eq!(newton.relaxation, Complex::new(0.5, 0.0));
+        assert!(newton.nova);
+        assert!(args.config.julia.is_some());
+        assert_eq!(
+            render("raw --polynomial '1 0 -1'")
+                .newton
+                .unwrap()
+                .roots()
+                .len(),
+            2
+        );
+        assert_errors(&[
+            ("raw --roots '1 -1' --polynomial '1 0'", "not both"),
+            ("raw --roots x", "for '--roots'"),
+            (
+                "raw --relaxation '1 2'",
+                "expected one number for '--relaxation'",
+            ),
+            ("raw --relaxation 0", "the relaxation must be"),
+            (
+                "raw --newton --formula tricorn",
+                "do not go with a Newton fractal",
+            ),
+            ("raw --newton --smooth", "do not go with a Newton fractal"),
+            ("raw --newton --julia 0,0", "--julia only goes with --nova"),
+            (
+                "explore --newton",
+                "the explorer does not draw Newton fractals",
+            ),
+        ]);
+    }
+
+    #[test]
+    fn buddhabrot() {
+        let args = render("raw -w 10 -H 8 --buddhabrot");
+        let buddhabrot = args.buddhabrot.unwrap();
+        assert_eq!(buddhabrot.samples, 10 * 8 * SAMPLES_PER_PIXEL);
+        assert!(buddhabrot.nebula.is_none() && !buddhabrot.anti);
+        assert!(args.newton.is_none());
+
+        let buddhabrot = render("raw --nebulabrot 50,500,5000 --samples 1000 --seed 7")
+            .buddhabrot
+            .unwrap();
+        assert_eq!(buddhabrot.nebula, Some([50, 500, 5000]));
+        assert_eq!((buddhabrot.samples, buddhabrot.seed), (1000, 7));
+        assert!(render("raw --anti-buddhabrot").buddhabrot.unwrap().anti);
+        assert_errors(&[
+            ("raw --nebulabrot 1,2", "expected '<red>,<green>,<blue>'"),
+            ("raw --samples 0", "at least 1 sample"),
+            ("raw --nebulabrot 1,0,1", "at least 1"),
+            ("raw --buddhabrot --periods", "do not go with a Buddhabrot"),
+            (
+                "raw --newton --buddhabrot",
+                "either a Newton fractal or a Buddhabrot",
+            ),
+            (
+                "raw --buddhabrot --julia 0,0",
+                "a Buddhabrot has no Julia sets",
+            ),
+            (
+                "explore --buddhabrot",
+                "the explorer does not draw Buddhabrots",
+            ),
+        ]);
+    }
+}
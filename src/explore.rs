@@ -0,0 +1,692 @@
+/*
+The interactive explorer: a full-screen view of the set that is moved around with the keyboard.
+
+  arrows or h/j/k/l   pan by a tenth of the view
+  + or =, -           zoom in and out around the center
+  ] and [             double or halve the iterations
+  p and P             next and previous palette
+  a                   switch between coloured cells and ascii characters
+  J                   switch to the Julia set of the point in the center, and back
+  f and F             next and previous formula, at its default view
+  r                   back to the starting view
+  u and U             undo and redo the last change of view
+  b                   save the view as a bookmark, under a name typed on the status line
+  g                   jump to a bookmark, or to a preset from the catalogue, by name
+  q, Esc or Ctrl-C    quit
+
+and with the mouse:
+
+  click               center the view on the clicked cell
+  wheel               zoom in and out, keeping the point under the cursor in place
+  drag                select a rectangle to zoom into
+
+Cells map to the plane through View::pixel_to_complex, the same transform calculate_mandelbrot
+uses, so a click lands exactly on the point that was calculated for that cell.
+
+The picture is drawn in two passes, so moving around never has to wait for a full render:
+first a coarse preview with one point for every 4x4 cells, then the full resolution a few rows at
+a time. Between batches of rows the keyboard is checked, and a key that changes the view throws
+away the rest of the render and starts over.
+*/
+
+use std::io::{self, Write};
+
+use num::complex::Complex;
+use rusty_mandelbrot::bookmarks::Bookmarks;
+use rusty_mandelbrot::location::History;
+use rusty_mandelbrot::mandelbrot::calculate_mandelbrot_row;
+use rusty_mandelbrot::presets::Preset;
+use rusty_mandelbrot::render::{ansi, ascii};
+use rusty_mandelbrot::terminal::{self, RawMode, TerminalSize};
+use rusty_mandelbrot::{parallel, Config, Formula, Fractal, Location, Palette, View};
+
+use crate::cli::Args;
+use crate::input::{
+    Input, InputParser, Mouse, MouseAction, MouseButton, CTRL_C, MOUSE_OFF, MOUSE_ON,
+};
+
+// how much one key press zooms in or out.
+const ZOOM_STEP: f64 = 1.5;
+// how far one key press pans, as a part of the view's width or height.
+const PAN_STEP: f64 = 0.1;
+// the preview has one point for every PREVIEW_BLOCK x PREVIEW_BLOCK cells.
+const PREVIEW_BLOCK: usize = 4;
+// how long to wait for a key when there is nothing left to render, in milliseconds.
+// It is also how quickly a resized window is noticed.
+const IDLE_POLL_MS: i32 = 200;
+
+// switches to the terminal's alternate screen and hides the cursor, and undoes both.
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let raw_mode = RawMode::enable()
+        .map_err(|error| format!("the explorer needs an interactive terminal: {}", error))?;
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let mut explorer = Explorer::new(args);
+
+    let result = write!(out, "{}{}", ENTER_SCREEN, MOUSE_ON)
+        .and_then(|_| explorer.run(&mut out))
+        .map_err(|error| error.to_string());
+
+    // leave the screen as it was, even when the loop failed.
+    let _ = write!(out, "{}{}", MOUSE_OFF, LEAVE_SCREEN).and_then(|_| out.flush());
+    drop(raw_mode);
+    result
+}
+
+struct Explorer<'a> {
+    args: &'a Args,
+    start: Location,
+    location: Location,
+    // how far the Mandelbrot set was zoomed in when J switched to a Julia set, to return to.
+    mandelbrot_zoom: f64,
+    history: History,
+    colors: bool,
+
+    // the bookmark name being typed, if any.
+    prompt: Option<Prompt>,
+    // shown on the status line until the next key press, e.g. "saved bookmark 'x'".
+    message: Option<String>,
+
+    terminal: TerminalSize,
+    // the render in progress, if any.
+    render: Option<Render>,
+    // the grid that is on screen: the preview, partly overwritten by the full-resolution rows.
+    shown: Vec<Vec<usize>>,
+
+    // the cell where a drag started, and the one the mouse is on now.
+    selection: Option<((usize, usize), (usize, usize))>,
+    // the selection rectangle as it is drawn on screen, so it can be erased.
+    drawn_selection: Option<Cells>,
+}
+
+// a bookmark name being typed on the status line, and what to do with it once enter is pressed.
+struct Prompt {
+    purpose: PromptPurpose,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptPurpose {
+    Save,
+    Jump,
+}
+
+// a rectangle of cells, with both corners included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cells {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+impl Cells {
+    fn between((x1, y1): (usize, usize), (x2, y2): (usize, usize)) -> Cells {
+        Cells {
+            left: x1.min(x2),
+            top: y1.min(y2),
+            right: x1.max(x2),
+            bottom: y1.max(y2),
+        }
+    }
+}
+
+// a render that is partly drawn: the preview, and the full-resolution rows above next_row.
+struct Render {
+    config: Config,
+    next_row: usize,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(args: &'a Args) -> Explorer<'a> {
+        Explorer {
+            args,
+            start: args.location.clone(),
+            location: args.location.clone(),
+            mandelbrot_zoom: 1.0,
+            history: History::default(),
+            colors: true,
+            prompt: None,
+            message: None,
+            terminal: current_size(),
+            render: None,
+            shown: Vec::new(),
+            selection: None,
+            drawn_selection: None,
+        }
+    }
+
+    fn run<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        self.restart(out)?;
+        let mut buffer = [0; 256];
+        let mut parser = InputParser::default();
+        loop {
+            // only wait for input when there is nothing to render.
+            let timeout = if self.render.is_some() {
+                0
+            } else {
+                IDLE_POLL_MS
+            };
+            if terminal::poll_input(timeout)? {
+                let read = terminal::read_input(&mut buffer)?;
+                let mut changed = false;
+                let mut selecting = false;
+                let mut status = false;
+                for input in parser.parse(&buffer[..read]) {
+                    match self.handle(input) {
+                        Action::Quit => return Ok(()),
+                        Action::Redraw => changed = true,
+                        Action::Select => selecting = true,
+                        Action::Status => status = true,
+                        Action::Nothing => {}
+                    }
+                }
+                if changed {
+                    self.restart(out)?;
+                } else if selecting || status {
+                    if selecting {
+                        self.draw_selection(out)?;
+                    }
+                    self.draw_status(out)?;
+                    out.flush()?;
+                }
+                continue;
+            }
+
+            let size = current_size();
+            if size != self.terminal {
+                self.terminal = size;
+                self.restart(out)?;
+                continue;
+            }
+
+            if self.render.is_some() {
+                self.render_batch(out)?;
+            }
+        }
+    }
+
+    fn handle(&mut self, input: Input) -> Action {
+        if self.prompt.is_some() {
+            return self.handle_prompt(input);
+        }
+        // a message only lasts until the next key press, not through mouse movements.
+        let cleared = !matches!(input, Input::Mouse(_)) && self.message.take().is_some();
+
+        match input {
+            Input::Char('u') => {
+                return match self.history.undo(self.location.clone()) {
+                    Some(previous) => {
+                        self.location = previous;
+                        Action::Redraw
+                    }
+                    None => self.show_message("nothing to undo".to_string()),
+                }
+            }
+            Input::Char('U') => {
+                return match self.history.redo(self.location.clone()) {
+                    Some(next) => {
+                        self.location = next;
+                        Action::Redraw
+                    }
+                    None => self.show_message("nothing to redo".to_string()),
+                }
+            }
+            Input::Char('b') => return self.start_prompt(PromptPurpose::Save),
+            Input::Char('g') => return self.start_prompt(PromptPurpose::Jump),
+            _ => {}
+        }
+
+        // every other change of location can be undone.
+        let before = self.location.clone();
+        let action = self.move_view(input);
+        if self.location != before {
+            self.history.push(before);
+        }
+        match action {
+            Action::Nothing if cleared => Action::Status,
+            action => action,
+        }
+    }
+
+    fn move_view(&mut self, input: Input) -> Action {
+        let view = self.view();
+        let pan_x = (view.real_max - view.real_min) * PAN_STEP;
+        let pan_y = (view.imaginary_max - view.imaginary_min) * PAN_STEP;
+        let location = &mut self.location;
+        match input {
+            Input::Char('q') | Input::Char(CTRL_C) | Input::Escape => return Action::Quit,
+            // row 0 is at imaginary_min, so up on the screen is towards smaller imaginary parts.
+            Input::Up | Input::Char('k') => location.center.im -= pan_y,
+            Input::Down | Input::Char('j') => location.center.im += pan_y,
+            Input::Left | Input::Char('h') => location.center.re -= pan_x,
+            Input::Right | Input::Char('l') => location.center.re += pan_x,
+            Input::Char('+') | Input::Char('=') => location.zoom *= ZOOM_STEP,
+            Input::Char('-') => location.zoom /= ZOOM_STEP,
+            Input::Char(']') => location.max_iterations = location.max_iterations.saturating_mul(2),
+            Input::Char('[') => location.max_iterations = (location.max_iterations / 2).max(1),
+            Input::Char('p') => location.palette = cycle(location.palette, 1),
+            Input::Char('P') => location.palette = cycle(location.palette, Palette::ALL.len() - 1),
+            Input::Char('a') => self.colors = !self.colors,
+            Input::Char('f') => change_formula(location, 1),
+            Input::Char('F') => change_formula(location, Fractal::ALL.len() - 1),
+            Input::Char('J') => match location.julia {
+                // the Julia set of the point in the middle of the screen, seen whole.
+                None => {
+                    self.mandelbrot_zoom = location.zoom;
+                    location.julia = Some(location.center);
+                    location.center = Complex::new(0.0, 0.0);
+                    location.zoom = 1.0;
+                }
+                // back to the Mandelbrot set, at the point this Julia set belongs to.
+                Some(c) => {
+                    location.julia = None;
+                    location.center = c;
+                    location.zoom = self.mandelbrot_zoom;
+                }
+            },
+            Input::Char('r') => *location = self.start.clone(),
+            Input::Mouse(mouse) => return self.handle_mouse(mouse, &view),
+            _ => return Action::Nothing,
+        }
+        Action::Redraw
+    }
+
+    fn start_prompt(&mut self, purpose: PromptPurpose) -> Action {
+        self.prompt = Some(Prompt {
+            purpose,
+            text: String::new(),
+        });
+        Action::Status
+    }
+
+    // typing a bookmark name: enter uses it, escape gives up, backspace takes back a character.
+    fn handle_prompt(&mut self, input: Input) -> Action {
+        let prompt = self.prompt.as_mut().unwrap();
+        match input {
+            Input::Char('\r') | Input::Char('\n') => {
+                let prompt = self.prompt.take().unwrap();
+                return self.use_bookmark(prompt.purpose, prompt.text.trim());
+            }
+            Input::Escape | Input::Char(CTRL_C) => self.prompt = None,
+            // terminals send either DEL or backspace for the backspace key.
+            Input::Char('\u{7f}') | Input::Char('\u{8}') => {
+                prompt.text.pop();
+            }
+            Input::Char(character) if !character.is_control() => prompt.text.push(character),
+            _ => return Action::Nothing,
+        }
+        Action::Status
+    }
+
+    fn use_bookmark(&mut self, purpose: PromptPurpose, name: &str) -> Action {
+        let path = &self.args.bookmarks;
+        let result = Bookmarks::load(path).and_then(|mut bookmarks| match purpose {
+            PromptPurpose::Save => {
+                bookmarks.insert(name, self.location.clone())?;
+                bookmarks.save(path)?;
+                Ok(None)
+            }
+            // a bookmark wins over a preset with the same name, since it was chosen on purpose.
+            PromptPurpose::Jump => match (bookmarks.get(name), Preset::find(name)) {
+                (Some(location), _) => Ok(Some(location.clone())),
+                (None, Some(preset)) => Ok(Some(preset.location.clone())),
+                (None, None) => Err(format!("no bookmark or preset named '{}'", name)),
+            },
+        });
+        match result {
+            Ok(Some(location)) => {
+                let before = std::mem::replace(&mut self.location, location);
+                self.history.push(before);
+                Action::Redraw
+            }
+            Ok(None) => self.show_message(format!("saved bookmark '{}'", name)),
+            Err(message) => self.show_message(message),
+        }
+    }
+
+    fn show_message(&mut self, message: String) -> Action {
+        self.message = Some(message);
+        Action::Status
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse, view: &View) -> Action {
+        // events on the status line are ignored, and drags are kept inside the picture.
+        let cell = (
+            mouse.column.min(view.width - 1),
+            mouse.row.min(view.height - 1),
+        );
+        match mouse.action {
+            MouseAction::Press(MouseButton::Left) if mouse.row < view.height => {
+                self.selection = Some((cell, cell));
+                Action::Nothing
+            }
+            MouseAction::Drag(MouseButton::Left) => match &mut self.selection {
+                Some((_, end)) => {
+                    *end = cell;
+                    Action::Select
+                }
+                None => Action::Nothing,
+            },
+            MouseAction::Release(MouseButton::Left) => {
+                let (start, end) = match self.selection.take() {
+                    Some(selection) => selection,
+                    None => return Action::Nothing,
+                };
+                if start == end {
+                    // a click: the clicked cell's point becomes the center.
+                    self.location.center = view.pixel_to_complex(start.0, start.1);
+                } else {
+                    self.zoom_into(view, Cells::between(start, end));
+                }
+                Action::Redraw
+            }
+            MouseAction::WheelUp if mouse.row < view.height => {
+                self.zoom_around(view, cell, ZOOM_STEP);
+                Action::Redraw
+            }
+            MouseAction::WheelDown if mouse.row < view.height => {
+                self.zoom_around(view, cell, 1.0 / ZOOM_STEP);
+                Action::Redraw
+            }
+            _ => Action::Nothing,
+        }
+    }
+
+    /*
+    Zooms by `factor`, keeping the point under `cell` where it is on screen. That point is
+    `factor` times closer to (or further from) the center afterwards, so the center moves towards
+    it by the same ratio.
+      Example:
+        // the point under the cursor is 1.0 to the right of the center, zooming in 2x.
+        new_center = point + (center - point) / 2   // 0.5 to the left of the point
+    */
+    fn zoom_around(&mut self, view: &View, cell: (usize, usize), factor: f64) {
+        let point = view.pixel_to_complex(cell.0, cell.1);
+        self.location.center = point + (self.location.center - point) / factor;
+        self.location.zoom *= factor;
+    }
+
+    // zooms in until the cells just fit the screen, with the middle of them as the new center.
+    fn zoom_into(&mut self, view: &View, cells: Cells) {
+        // the far corner is the first point past the last cell, so the last cell is included.
+        let top_left = view.pixel_to_complex(cells.left, cells.top);
+        let bottom_right = view.pixel_to_complex(cells.right + 1, cells.bottom + 1);
+        self.location.center = (top_left + bottom_right) / 2.0;
+        let factor = f64::min(
+            (view.real_max - view.real_min) / (bottom_right.re - top_left.re),
+            (view.imaginary_max - view.imaginary_min) / (bottom_right.im - top_left.im),
+        );
+        self.location.zoom *= factor;
+    }
+
+    // the view that fills the screen, minus the status line.
+    fn view(&self) -> View {
+        let height = self.terminal.rows.saturating_sub(1).max(1);
+        self.location
+            .view(self.terminal.columns, height, self.args.cell_aspect)
+    }
+
+    // throws away the render in progress, and starts a new one with a preview.
+    fn restart<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let mut config = self.args.config.clone();
+        config.view = self.view();
+        config.max_iterations = self.location.max_iterations;
+        config.julia = self.location.julia;
+        config.formula = self.location.formula.clone();
+        // the bailout has to suit the formula, unless it was set for this one on purpose.
+        if config.formula != self.args.config.formula {
+            config.bailout = config.formula.default_bailout();
+        }
+
+        // the preview: the same view, with a point for every block of cells.
+        let mut preview_config = config.clone();
+        preview_config.view.width = config.view.width.div_ceil(PREVIEW_BLOCK);
+        preview_config.view.height = config.view.height.div_ceil(PREVIEW_BLOCK);
+        let coarse = parallel::map_rows(preview_config.view.height, self.args.threads, |pixel_y| {
+            calculate_mandelbrot_row(&preview_config, pixel_y)
+        });
+        let preview: Vec<Vec<usize>> = (0..config.view.height)
+            .map(|pixel_y| {
+                (0..config.view.width)
+                    .map(|pixel_x| coarse[pixel_y / PREVIEW_BLOCK][pixel_x / PREVIEW_BLOCK])
+                    .collect()
+            })
+            .collect();
+
+        write!(out, "\x1b[2J")?;
+        self.draw_rows(out, &preview, 0)?;
+        self.shown = preview;
+        self.drawn_selection = None;
+        self.render = Some(Render {
+            config,
+            next_row: 0,
+        });
+        self.draw_status(out)?;
+        out.flush()
+    }
+
+    // computes and draws the next few full-resolution rows.
+    fn render_batch<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let render = match &self.render {
+            Some(render) => render,
+            None => return Ok(()),
+        };
+        let height = render.config.view.height;
+        let first = render.next_row;
+        let count = (self.args.threads * 2).min(height - first);
+        let rows = parallel::map_rows(count, self.args.threads, |offset| {
+            calculate_mandelbrot_row(&render.config, first + offset)
+        });
+
+        self.draw_rows(out, &rows, first)?;
+        self.shown.splice(first..first + count, rows);
+        if self.selection.is_some() {
+            // the new rows may have been drawn over the selection.
+            self.drawn_selection = None;
+            self.draw_selection(out)?;
+        }
+        let render = self.render.as_mut().unwrap();
+        render.next_row += count;
+        if render.next_row >= height {
+            self.render = None;
+        }
+        self.draw_status(out)?;
+        out.flush()
+    }
+
+    // draws the rows with the chosen renderer, the first of them on screen row `first`.
+    fn draw_rows<W: Write>(
+        &self,
+        out: &mut W,
+        rows: &[Vec<usize>],
+        first: usize,
+    ) -> io::Result<()> {
+        let mut lines = Vec::new();
+        if self.colors {
+            ansi::render_ansi(
+                rows,
+                self.location.max_iterations,
+                self.location.palette,
+                self.args.color_depth,
+                &mut lines,
+            )?;
+        } else {
+            ascii::render_mandelbrot(
+                rows,
+                self.location.max_iterations,
+                &self.args.ramp,
+                self.args.scale,
+                &mut lines,
+            )?;
+        }
+
+        // the renderers end every row with a newline. Instead, each row is put in its place.
+        for (offset, line) in lines
+            .split(|&byte| byte == b'\n')
+            .take(rows.len())
+            .enumerate()
+        {
+            write!(out, "\x1b[{};1H", first + offset + 1)?;
+            out.write_all(line)?;
+        }
+        Ok(())
+    }
+
+    // erases the old selection rectangle by drawing its rows again, and draws the new one.
+    fn draw_selection<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        if let Some(old) = self.drawn_selection.take() {
+            let rows = &self.shown[old.top..=old.bottom];
+            self.draw_rows(out, rows, old.top)?;
+        }
+        let cells = match self.selection {
+            Some((start, end)) => Cells::between(start, end),
+            None => return Ok(()),
+        };
+
+        // the outline, in reverse video. Screen positions count from 1.
+        for pixel_y in cells.top..=cells.bottom {
+            for pixel_x in cells.left..=cells.right {
+                let on_edge = pixel_y == cells.top
+                    || pixel_y == cells.bottom
+                    || pixel_x == cells.left
+                    || pixel_x == cells.right;
+                if on_edge {
+                    write!(
+                        out,
+                        "\x1b[{};{}H\x1b[0;7m \x1b[0m",
+                        pixel_y + 1,
+                        pixel_x + 1
+                    )?;
+                }
+            }
+        }
+        self.drawn_selection = Some(cells);
+        Ok(())
+    }
+
+    // the last row of the screen: where the view is, and how far the render has come.
+    // while a bookmark name is typed, the status line is the prompt for it instead.
+    fn draw_status<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if let Some(prompt) = &self.prompt {
+            let question = match prompt.purpose {
+                PromptPurpose::Save => "save bookmark as",
+                PromptPurpose::Jump => "go to bookmark",
+            };
+            let line = format!(
+                " {}: {}_  (enter to confirm, esc to cancel)",
+                question, prompt.text
+            );
+            return self.write_status(out, &line);
+        }
+
+        let progress = match &self.render {
+            Some(render) => format!(
+                "  rendering {}%",
+                render.next_row * 100 / render.config.view.height
+            ),
+            None => String::new(),
+        };
+        let help = match &self.message {
+            Some(message) => message.as_str(),
+            None => "arrows pan, +/- zoom, [/] iterations, p palette, a ascii, J julia, f formula, u/U undo/redo, b bookmark, g go to, r reset, q quit, mouse: click center, wheel zoom, drag select",
+        };
+        let formula = match &self.location.formula {
+            Fractal::Mandelbrot => String::new(),
+            Fractal::Multibrot(power) => format!(" multibrot z^{} ", power),
+            Fractal::Custom(expression) => format!(" z -> {} ", expression),
+            formula => format!(" {} ", formula.name()),
+        };
+        let julia = match self.location.julia {
+            Some(c) => format!("{} julia {:+.12} {:+.12}i ", formula, c.re, c.im),
+            None => formula,
+        };
+        let status = format!(
+            "{} center {:+.12} {:+.12}i  zoom {:.4e}  iterations {}  palette {}{}  | {}",
+            julia,
+            self.location.center.re,
+            self.location.center.im,
+            self.location.zoom,
+            self.location.max_iterations,
+            self.location.palette.name(),
+            progress,
+            help,
+        );
+        self.write_status(out, &status)
+    }
+
+    fn write_status<W: Write>(&self, out: &mut W, status: &str) -> io::Result<()> {
+        let status: String = status.chars().take(self.terminal.columns).collect();
+
+        // reverse video, and cleared to the end of the line.
+        write!(
+            out,
+            "\x1b[{};1H\x1b[7m{:width$}\x1b[0m",
+            self.terminal.rows,
+            status,
+            width = self.terminal.columns
+        )
+    }
+}
+
+enum Action {
+    Quit,
+    // the view changed, so everything is rendered again.
+    Redraw,
+    // only the selection rectangle changed.
+    Select,
+    // only the status line changed.
+    Status,
+    Nothing,
+}
+
+fn current_size() -> TerminalSize {
+    TerminalSize::detect().unwrap_or(TerminalSize {
+        columns: 80,
+        rows: 24,
+        pixel_width: 0,
+        pixel_height: 0,
+    })
+}
+
+// switches to the formula `steps` places further along Fractal::ALL, and to the view that shows
+// all of it (or for a Julia set, to the whole Julia set of the same point).
+fn change_formula(location: &mut Location, steps: usize) {
+    let next = match Fractal::ALL
+        .iter()
+        .position(|fractal| fractal.name() == location.formula.name())
+    {
+        Some(index) => (index + steps) % Fractal::ALL.len(),
+        // a formula that was typed in is left for the Mandelbrot set, either way.
+        None => 0,
+    };
+    location.formula = Fractal::ALL[next].clone();
+    let whole = Location::fitting(
+        &location.formula.default_view(),
+        location.max_iterations,
+        location.palette,
+    );
+    match location.julia {
+        Some(_) => {
+            location.center = Complex::new(0.0, 0.0);
+            location.zoom = 1.0;
+        }
+        None => {
+            location.center = whole.center;
+            location.zoom = whole.zoom;
+        }
+    }
+}
+
+// the palette `steps` places further along Palette::ALL, wrapping around at the end.
+fn cycle(palette: Palette, steps: usize) -> Palette {
+    let index = Palette::ALL
+        .iter()
+        .position(|&other| other == palette)
+        .unwrap_or(0);
+    Palette::ALL[(index + steps) % Palette::ALL.len()]
+}
@@ -0,0 +1,766 @@
+//! Formulas typed in at runtime, such as `z^3 - z + c` or `sin(z)*c`.
+//!
+//! An [`Expression`] is parsed once, into a tree of closures, which then runs for every step of
+//! every point. Everything in it is a complex number:
+//!
+//! - the variables `z`, `c`, `pixel` (the point of the pixel: c for the Mandelbrot set, and the
+//!   starting z for a Julia set) and `n` (the number of steps taken so far)
+//! - numbers such as `3`, `0.5` or `1e-3`, imaginary ones such as `2i`, and the constants `i`,
+//!   `pi` and `e`
+//! - `+`, `-`, `*`, `/`, and `^` for powers, which binds tighter than a minus in front:
+//!   `-z^2` is `-(z^2)`
+//! - the functions `exp`, `log`, `sqrt`, `sin`, `cos`, `tan`, `sinh`, `cosh`, `tanh`, `conj`,
+//!   `abs` (the distance to 0), `re`, `im` and `pow(a, b)`
+//!
+//! z starts where it does for z² + c: at 0, or at the pixel for a Julia set. A formula that
+//! needs to start elsewhere says so after a semicolon, as in `sin(z)*c; z0 = c`, where 0 would
+//! stay 0 forever.
+//!
+//! ```
+//! use num::complex::Complex;
+//! use rusty_mandelbrot::expression::Expression;
+//!
+//! let cubic = Expression::parse("z^3 - z + c").unwrap();
+//! let z = Complex::new(0.5, 1.0);
+//! let c = Complex::new(-0.1, 0.2);
+//! assert_eq!(cubic.evaluate(z, c, c, 0), z * z * z - z + c);
+//!
+//! assert!(Expression::parse("sin(z) * q").is_err());
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use num::complex::Complex;
+
+use crate::formula::Formula;
+use crate::view::View;
+
+// how deep expressions may nest, and operations chain. Both the parser and the compiled formula
+// go down the tree one call per level, so this keeps them from running out of stack.
+const MAX_DEPTH: usize = 100;
+
+/// A formula for z, parsed and compiled, ready to be iterated. See the [module](self) for what
+/// it may contain.
+#[derive(Clone)]
+pub struct Expression {
+    source: String,
+    step: Arc<Compiled>,
+    /// Where z starts, if the formula says.
+    start: Option<Arc<Compiled>>,
+    degree: Option<f64>,
+    uses_iteration: bool,
+}
+
+impl Expression {
+    /// Parses and compiles `source`, or describes what is wrong with it.
+    pub fn parse(source: &str) -> Result<Expression, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            next: 0,
+            depth: 0,
+        };
+        let step = parser.sum()?;
+        let start = if parser.symbol_of(&[';']).is_some() {
+            parser.expect_name("z0")?;
+            parser.expect('=')?;
+            Some(parser.sum()?)
+        } else {
+            None
+        };
+        if let Some(token) = parser.tokens.get(parser.next) {
+            return Err(format!(
+                "unexpected {} at character {}",
+                token.kind, token.position
+            ));
+        }
+        Ok(Expression {
+            source: source.trim().to_string(),
+            step: Arc::from(step.compile()),
+            start: start.map(|start| Arc::from(start.compile())),
+            degree: step.degree(),
+            uses_iteration: step.uses(Variable::Iteration),
+        })
+    }
+
+    /// The text the expression was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Runs the expression for these values of its variables, for the next value of z.
+    pub fn evaluate(
+        &self,
+        z: Complex<f64>,
+        c: Complex<f64>,
+        pixel: Complex<f64>,
+        iteration: usize,
+    ) -> Complex<f64> {
+        (self.step)(&Variables {
+            z,
+            c,
+            pixel,
+            iteration: iteration as f64,
+        })
+    }
+}
+
+// the closures cannot be compared or printed, but they follow from the source, which can.
+impl PartialEq for Expression {
+    fn eq(&self, other: &Expression) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Expression").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Formula for Expression {
+    // without a pixel, c stands in for it, as it does for the Mandelbrot set.
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        self.evaluate(z, c, c, 0)
+    }
+
+    fn step_at(
+        &self,
+        z: Complex<f64>,
+        c: Complex<f64>,
+        pixel: Complex<f64>,
+        iteration: usize,
+    ) -> Complex<f64> {
+        self.evaluate(z, c, pixel, iteration)
+    }
+
+    fn start(&self, z: Complex<f64>, c: Complex<f64>, pixel: Complex<f64>) -> Complex<f64> {
+        match &self.start {
+            Some(start) => start(&Variables {
+                z,
+                c,
+                pixel,
+                iteration: 0.0,
+            }),
+            None => z,
+        }
+    }
+
+    // a polynomial in z grows as fast as its highest power. For anything else there is no one
+    // degree, and the smooth colouring falls back to that of z² + c.
+    fn degree(&self) -> f64 {
+        self.degree.filter(|&degree| degree > 1.0).unwrap_or(2.0)
+    }
+
+    // an expression can put its fractal anywhere, so the view is centered on 0, with room on all
+    // sides.
+    fn default_view(&self) -> View {
+        View::new(-3.0, 3.0, -2.0, 2.0, 230, 66)
+    }
+
+    fn uses_iteration(&self) -> bool {
+        self.uses_iteration
+    }
+}
+
+/*
+The parser turns the text into a tree, such as this one for `z^3 - z + c`:
+
+            +
+          /   \
+         -     c
+       /   \
+     ^3     z
+     |
+     z
+
+which is then compiled into a closure for every operation, each calling the closures of its
+operands. Calling through a tree of closures keeps the values in registers, where a program for
+a stack machine would have to store every one of them on its stack and read it back.
+*/
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Constant(Complex<f64>),
+    Variable(Variable),
+    Unary(Unary, Box<Node>),
+    Binary(Binary, Box<Node>, Box<Node>),
+}
+
+type Compiled = dyn Fn(&Variables) -> Complex<f64> + Send + Sync;
+
+impl Node {
+    // the operations below are worked out straight away when all they take are constants, so
+    // that `2*pi` costs nothing while iterating.
+    fn unary(operation: Unary, operand: Node) -> Node {
+        match operand {
+            Node::Constant(value) => Node::Constant(operation.apply(value)),
+            operand => Node::Unary(operation, Box::new(operand)),
+        }
+    }
+
+    fn binary(operation: Binary, left: Node, right: Node) -> Node {
+        match (left, right) {
+            (Node::Constant(left), Node::Constant(right)) => {
+                Node::Constant(operation.apply(left, right))
+            }
+            // a whole power known in advance is done by repeated multiplication, so that `z^2`
+            // is exactly `z * z`.
+            (left, Node::Constant(power))
+                if operation == Binary::Power
+                    && power.im == 0.0
+                    && power.re.fract() == 0.0
+                    && power.re.abs() <= i32::MAX as f64 =>
+            {
+                Node::unary(Unary::PowerOf(power.re as i32), left)
+            }
+            (left, right) => Node::Binary(operation, Box::new(left), Box::new(right)),
+        }
+    }
+
+    fn uses(&self, variable: Variable) -> bool {
+        match self {
+            Node::Constant(_) => false,
+            Node::Variable(used) => *used == variable,
+            Node::Unary(_, operand) => operand.uses(variable),
+            Node::Binary(_, left, right) => left.uses(variable) || right.uses(variable),
+        }
+    }
+
+    // how the value grows with z: its degree as a polynomial in z, or `None` if it is not one.
+    fn degree(&self) -> Option<f64> {
+        match self {
+            Node::Constant(_) => Some(0.0),
+            Node::Variable(Variable::Z) => Some(1.0),
+            Node::Variable(_) => Some(0.0),
+            Node::Unary(operation, operand) => {
+                let degree = operand.degree();
+                match operation {
+                    Unary::Negate => degree,
+                    Unary::PowerOf(power) if *power >= 0 => degree.map(|d| d * *power as f64),
+                    Unary::Function(function) if function.keeps_degree() => degree,
+                    // a constant stays one, whatever is done to it.
+                    _ => degree.filter(|&d| d == 0.0),
+                }
+            }
+            Node::Binary(operation, left, right) => {
+                let (left_degree, right_degree) = (left.degree(), right.degree());
+                match (operation, &**right) {
+                    (Binary::Add | Binary::Subtract, _) => {
+                        left_degree.zip(right_degree).map(|(l, r)| l.max(r))
+                    }
+                    (Binary::Multiply, _) => left_degree.zip(right_degree).map(|(l, r)| l + r),
+                    // only dividing by a constant keeps a polynomial one.
+                    (Binary::Divide, _) => left_degree.filter(|_| right_degree == Some(0.0)),
+                    (Binary::Power, Node::Constant(power))
+                        if power.im == 0.0 && power.re >= 0.0 =>
+                    {
+                        left_degree.map(|d| d * power.re)
+                    }
+                    (Binary::Power, _) => left_degree
+                        .zip(right_degree)
+                        .filter(|&degrees| degrees == (0.0, 0.0))
+                        .map(|_| 0.0),
+                }
+            }
+        }
+    }
+
+    fn compile(&self) -> Box<Compiled> {
+        match *self {
+            Node::Constant(value) => Box::new(move |_| value),
+            Node::Variable(variable) => Box::new(move |variables| variable.value(variables)),
+            Node::Unary(operation, ref operand) => {
+                let operand = Operand::compile(operand);
+                Box::new(move |variables| operation.apply(operand.value(variables)))
+            }
+            Node::Binary(operation, ref left, ref right) => {
+                let left = Operand::compile(left);
+                let right = Operand::compile(right);
+                Box::new(move |variables| {
+                    operation.apply(left.value(variables), right.value(variables))
+                })
+            }
+        }
+    }
+}
+
+// an operand of a compiled operation. Variables and numbers are read in place, which saves a call
+// for each of them.
+enum Operand {
+    Constant(Complex<f64>),
+    Variable(Variable),
+    Compiled(Box<Compiled>),
+}
+
+impl Operand {
+    fn compile(node: &Node) -> Operand {
+        match *node {
+            Node::Constant(value) => Operand::Constant(value),
+            Node::Variable(variable) => Operand::Variable(variable),
+            _ => Operand::Compiled(node.compile()),
+        }
+    }
+
+    fn value(&self, variables: &Variables) -> Complex<f64> {
+        match self {
+            Operand::Constant(value) => *value,
+            Operand::Variable(variable) => variable.value(variables),
+            Operand::Compiled(compiled) => compiled(variables),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    Z,
+    C,
+    Pixel,
+    Iteration,
+}
+
+impl Variable {
+    fn value(self, variables: &Variables) -> Complex<f64> {
+        match self {
+            Variable::Z => variables.z,
+            Variable::C => variables.c,
+            Variable::Pixel => variables.pixel,
+            Variable::Iteration => Complex::new(variables.iteration, 0.0),
+        }
+    }
+}
+
+// the values of the variables for one step.
+struct Variables {
+    z: Complex<f64>,
+    c: Complex<f64>,
+    pixel: Complex<f64>,
+    iteration: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unary {
+    Negate,
+    /// A whole power, by repeated multiplication.
+    PowerOf(i32),
+    Function(Function),
+}
+
+impl Unary {
+    fn apply(self, value: Complex<f64>) -> Complex<f64> {
+        match self {
+            Unary::Negate => -value,
+            Unary::PowerOf(power) => value.powi(power),
+            Unary::Function(function) => function.apply(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Binary {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+impl Binary {
+    fn apply(self, left: Complex<f64>, right: Complex<f64>) -> Complex<f64> {
+        match self {
+            Binary::Add => left + right,
+            Binary::Subtract => left - right,
+            Binary::Multiply => left * right,
+            Binary::Divide => left / right,
+            Binary::Power => power(left, right),
+        }
+    }
+}
+
+// a^b. Real powers go through polar form, and only complex ones through the logarithm, which
+// has no value at 0.
+fn power(base: Complex<f64>, exponent: Complex<f64>) -> Complex<f64> {
+    if exponent.im != 0.0 {
+        if base == Complex::new(0.0, 0.0) {
+            return base;
+        }
+        base.powc(exponent)
+    } else if exponent.re.fract() == 0.0 && exponent.re.abs() <= i32::MAX as f64 {
+        base.powi(exponent.re as i32)
+    } else {
+        base.powf(exponent.re)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Exp,
+    Log,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Conj,
+    Abs,
+    Re,
+    Im,
+}
+
+impl Function {
+    const ALL: [Function; 13] = [
+        Function::Exp,
+        Function::Log,
+        Function::Sqrt,
+        Function::Sin,
+        Function::Cos,
+        Function::Tan,
+        Function::Sinh,
+        Function::Cosh,
+        Function::Tanh,
+        Function::Conj,
+        Function::Abs,
+        Function::Re,
+        Function::Im,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Function::Exp => "exp",
+            Function::Log => "log",
+            Function::Sqrt => "sqrt",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Conj => "conj",
+            Function::Abs => "abs",
+            Function::Re => "re",
+            Function::Im => "im",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Function> {
+        Function::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+    }
+
+    fn apply(self, value: Complex<f64>) -> Complex<f64> {
+        match self {
+            Function::Exp => value.exp(),
+            Function::Log => value.ln(),
+            Function::Sqrt => value.sqrt(),
+            Function::Sin => value.sin(),
+            Function::Cos => value.cos(),
+            Function::Tan => value.tan(),
+            Function::Sinh => value.sinh(),
+            Function::Cosh => value.cosh(),
+            Function::Tanh => value.tanh(),
+            Function::Conj => value.conj(),
+            Function::Abs => Complex::new(value.norm(), 0.0),
+            Function::Re => Complex::new(value.re, 0.0),
+            Function::Im => Complex::new(value.im, 0.0),
+        }
+    }
+
+    // these keep the size of their argument, so a polynomial stays one of the same degree.
+    fn keeps_degree(self) -> bool {
+        matches!(
+            self,
+            Function::Conj | Function::Abs | Function::Re | Function::Im
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    /// Counted in characters from 1, for the error messages.
+    position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(Complex<f64>),
+    Name(String),
+    Symbol(char),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Number(_) => f.write_str("number"),
+            TokenKind::Name(name) => write!(f, "'{}'", name),
+            TokenKind::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < characters.len() {
+        let character = characters[index];
+        let position = index + 1;
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        let kind = if character.is_ascii_digit() || character == '.' {
+            // digits with at most one point, then maybe an exponent, like 1.5e-3.
+            let start = index;
+            while index < characters.len()
+                && (characters[index].is_ascii_digit() || characters[index] == '.')
+            {
+                index += 1;
+            }
+            if index < characters.len() && characters[index] == 'e' {
+                let mut end = index + 1;
+                if end < characters.len() && matches!(characters[end], '+' | '-') {
+                    end += 1;
+                }
+                // only an exponent if digits follow, so that 2e reads as 2 and then e.
+                if end < characters.len() && characters[end].is_ascii_digit() {
+                    index = end;
+                    while index < characters.len() && characters[index].is_ascii_digit() {
+                        index += 1;
+                    }
+                }
+            }
+            let text: String = characters[start..index].iter().collect();
+            let value: f64 = text
+                .parse()
+                .map_err(|_| format!("invalid number '{}' at character {}", text, position))?;
+            // a number right before an i is imaginary, as in 2i, unless the i starts a name.
+            let imaginary = index < characters.len()
+                && characters[index] == 'i'
+                && !characters
+                    .get(index + 1)
+                    .is_some_and(|next| next.is_ascii_alphanumeric());
+            if imaginary {
+                index += 1;
+                TokenKind::Number(Complex::new(0.0, value))
+            } else {
+                TokenKind::Number(Complex::new(value, 0.0))
+            }
+        } else if character.is_ascii_alphabetic() {
+            let start = index;
+            while index < characters.len()
+                && (characters[index].is_ascii_alphanumeric() || characters[index] == '_')
+            {
+                index += 1;
+            }
+            TokenKind::Name(characters[start..index].iter().collect())
+        } else if "+-*/^(),;=".contains(character) {
+            index += 1;
+            TokenKind::Symbol(character)
+        } else {
+            return Err(format!(
+                "unexpected '{}' at character {}",
+                character, position
+            ));
+        };
+        tokens.push(Token { kind, position });
+    }
+    Ok(tokens)
+}
+
+/*
+A recursive descent parser, with one method for each level of precedence, from loosest to
+tightest:
+
+  sum     = product (("+" | "-") product)*
+  product = negated (("*" | "/") negated)*
+  negated = "-" negated | power
+  power   = atom ("^" negated)?
+  atom    = number | name | name "(" sum ("," sum)* ")" | "(" sum ")"
+
+and after the formula for the step, optionally `";" "z0" "=" sum` for the start.
+*/
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    // how deep the tree being built is at this point, at most: every level of `negated`, which
+    // every kind of nesting goes through, and every operation chained onto a sum or product,
+    // which each put the operations before them one level further down.
+    depth: usize,
+}
+
+impl Parser {
+    fn sum(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut node = self.product()?;
+        while let Some(symbol) = self.symbol_of(&['+', '-']) {
+            self.deeper()?;
+            let operation = match symbol {
+                '+' => Binary::Add,
+                _ => Binary::Subtract,
+            };
+            node = Node::binary(operation, node, self.product()?);
+        }
+        self.depth = depth;
+        Ok(node)
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut node = self.negated()?;
+        while let Some(symbol) = self.symbol_of(&['*', '/']) {
+            self.deeper()?;
+            let operation = match symbol {
+                '*' => Binary::Multiply,
+                _ => Binary::Divide,
+            };
+            node = Node::binary(operation, node, self.negated()?);
+        }
+        self.depth = depth;
+        Ok(node)
+    }
+
+    fn negated(&mut self) -> Result<Node, String> {
+        self.deeper()?;
+        let node = if self.symbol_of(&['-']).is_some() {
+            self.negated().map(|node| Node::unary(Unary::Negate, node))
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        node
+    }
+
+    // goes one level further down the tree, unless that is too deep. A tree is never built past
+    // MAX_DEPTH, so nothing that walks it afterwards can run out of stack.
+    fn deeper(&mut self) -> Result<(), String> {
+        if self.depth == MAX_DEPTH {
+            return Err("the formula is nested too deeply".to_string());
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.symbol_of(&['^']).is_none() {
+            return Ok(base);
+        }
+        Ok(Node::binary(Binary::Power, base, self.negated()?))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let token = match self.tokens.get(self.next) {
+            Some(token) => token.clone(),
+            None => return Err("the formula ends too early".to_string()),
+        };
+        self.next += 1;
+        match token.kind {
+            TokenKind::Number(value) => Ok(Node::Constant(value)),
+            TokenKind::Symbol('(') => {
+                let node = self.sum()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            TokenKind::Name(name) if self.symbol_of(&['(']).is_some() => {
+                self.call(&name, token.position)
+            }
+            TokenKind::Name(name) => match &*name {
+                "z" => Ok(Node::Variable(Variable::Z)),
+                "c" => Ok(Node::Variable(Variable::C)),
+                "pixel" => Ok(Node::Variable(Variable::Pixel)),
+                "n" => Ok(Node::Variable(Variable::Iteration)),
+                "i" => Ok(Node::Constant(Complex::new(0.0, 1.0))),
+                "pi" => Ok(Node::Constant(Complex::new(std::f64::consts::PI, 0.0))),
+                "e" => Ok(Node::Constant(Complex::new(std::f64::consts::E, 0.0))),
+                _ => Err(format!(
+                    "unknown name '{}' at character {} (the variables are z, c, pixel and n)",
+                    name, token.position
+                )),
+            },
+            kind => Err(format!(
+                "unexpected {} at character {}",
+                kind, token.position
+            )),
+        }
+    }
+
+    // a function call, after the opening parenthesis.
+    fn call(&mut self, name: &str, position: usize) -> Result<Node, String> {
+        if name == "pow" {
+            let base = self.sum()?;
+            self.expect(',')?;
+            let exponent = self.sum()?;
+            self.expect(')')?;
+            return Ok(Node::binary(Binary::Power, base, exponent));
+        }
+        let function = Function::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = Function::ALL.iter().map(|f| f.name()).collect();
+            format!(
+                "unknown function '{}' at character {} (one of: {}, pow)",
+                name,
+                position,
+                names.join(", ")
+            )
+        })?;
+        let argument = self.sum()?;
+        if self.symbol_of(&[',']).is_some() {
+            return Err(format!("'{}' takes a single argument", name));
+        }
+        self.expect(')')?;
+        Ok(Node::unary(Unary::Function(function), argument))
+    }
+
+    // the next token, if it is one of `symbols`.
+    fn symbol_of(&mut self, symbols: &[char]) -> Option<char> {
+        match self.tokens.get(self.next) {
+            Some(Token {
+                kind: TokenKind::Symbol(symbol),
+                ..
+            }) if symbols.contains(symbol) => {
+                self.next += 1;
+                Some(*symbol)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_name(&mut self, expected: &str) -> Result<(), String> {
+        match self.tokens.get(self.next) {
+            Some(Token {
+                kind: TokenKind::Name(name),
+                ..
+            }) if name == expected => {
+                self.next += 1;
+                Ok(())
+            }
+            Some(token) => Err(format!(
+                "expected '{}' at character {}, found {}",
+                expected, token.position, token.kind
+            )),
+            None => Err(format!("expected '{}' at the end", expected)),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.symbol_of(&[symbol]).is_some() {
+            return Ok(());
+        }
+        match self.tokens.get(self.next) {
+            Some(token) => Err(format!(
+                "expected '{}' at character {}, found {}",
+                symbol, token.position, token.kind
+            )),
+            None => Err(format!("expected '{}' at the end", symbol)),
+        }
+    }
+}
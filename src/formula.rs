@@ -0,0 +1,372 @@
+//! The equations the escape-time engine can iterate, besides the classic z² + c.
+//!
+//! Each one is a [`Formula`]: a single step of the iteration, with the view and bailout that
+//! suit it. The engine in [`mandelbrot`](crate::mandelbrot) is generic over the trait, and
+//! [`Fractal`] picks one of them at runtime, by name, or takes an [`Expression`] typed in
+//! instead.
+
+use std::sync::Arc;
+
+use num::complex::Complex;
+
+use crate::bailout::Bailout;
+use crate::expression::Expression;
+use crate::view::View;
+
+/// One step of an escape-time iteration, and how to show its fractal.
+pub trait Formula {
+    /// The next value of `z` for the point `c`.
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64>;
+
+    /// Same as [`Formula::step`], for formulas that also depend on the point of the pixel (c
+    /// for the Mandelbrot set, the starting z for a Julia set) or on the number of steps taken
+    /// before this one. This is the one the engine calls, and by default it ignores both.
+    fn step_at(
+        &self,
+        z: Complex<f64>,
+        c: Complex<f64>,
+        _pixel: Complex<f64>,
+        _iteration: usize,
+    ) -> Complex<f64> {
+        self.step(z, c)
+    }
+
+    /// Where z starts, given where it would for z² + c: at 0, or at the pixel for a Julia set.
+    fn start(&self, z: Complex<f64>, _c: Complex<f64>, _pixel: Complex<f64>) -> Complex<f64> {
+        z
+    }
+
+    /// The power z is raised to in every step, which decides how quickly |z| grows once it is
+    /// large. The smooth colouring needs it to cancel out the bands.
+    fn degree(&self) -> f64 {
+        2.0
+    }
+
+    /// The view that shows the whole fractal.
+    fn default_view(&self) -> View {
+        View::default()
+    }
+
+    /// A bailout that no orbit of a point inside the fractal ever passes.
+    fn default_bailout(&self) -> Bailout {
+        Bailout::default()
+    }
+
+    /// Whether the main cardioid and the period-2 bulb of the Mandelbrot set are inside this
+    /// fractal too, so the engine can skip the points in them. Only true for z² + c itself.
+    fn has_mandelbrot_interior(&self) -> bool {
+        false
+    }
+
+    /// Whether a step depends on the number of steps before it. If so, z coming back to an
+    /// earlier value does not mean its orbit repeats, and the engine stops looking for cycles.
+    fn uses_iteration(&self) -> bool {
+        false
+    }
+}
+
+/*
+All the quadratic variants below start from z² = (x + iy)² = (x² - y²) + i(2xy), and change the
+signs of the parts along the way. Taking the absolute value of a part folds the plane over an
+axis, which breaks the symmetry of the Mandelbrot set and gives each of them its own shapes.
+*/
+
+/// The Mandelbrot set: z² + c.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Mandelbrot;
+
+impl Formula for Mandelbrot {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        z * z + c
+    }
+
+    fn has_mandelbrot_interior(&self) -> bool {
+        true
+    }
+}
+
+/// The Multibrot sets: z^d + c, for any power d greater than 1.
+///
+/// Whole powers are computed by repeated multiplication, and are as exact as z² + c. Any other
+/// power goes through polar form, which has a branch cut along the negative real axis, so those
+/// sets are cut off along it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Multibrot {
+    pub power: f64,
+}
+
+impl Formula for Multibrot {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        if self.power.fract() == 0.0 && self.power <= i32::MAX as f64 {
+            z.powi(self.power as i32) + c
+        } else {
+            z.powf(self.power) + c
+        }
+    }
+
+    fn degree(&self) -> f64 {
+        self.power
+    }
+
+    // every Multibrot set has d - 1 fold symmetry around 0, and lies inside the disc of radius
+    // 2^(1/(d-1)) (see default_bailout), which shrinks towards 1 as d grows. For powers below 2
+    // the disc is far too big, so the view is kept to the one of d = 2.
+    fn default_view(&self) -> View {
+        let radius = 2f64.powf(1.0 / (self.power - 1.0)).min(2.0);
+        View::new(-radius, radius, -radius, radius, 230, 66)
+    }
+
+    /*
+    Once |z| > 1 and |z|^(d-1) > 2, every step at least doubles |z| minus |c|, so the orbit can
+    never come back. The set itself lies inside |c| <= 2^(1/(d-1)), so the radius where both hold
+    is max(2, 2^(1/(d-1))): 2 for d >= 2, and more for the powers between 1 and 2.
+    */
+    fn default_bailout(&self) -> Bailout {
+        let radius = f64::max(2.0, 2f64.powf(1.0 / (self.power - 1.0)));
+        Bailout::new(radius, Default::default())
+    }
+}
+
+/// The Burning Ship: (|x| + i|y|)² + c. Both parts are folded before squaring.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BurningShip;
+
+impl Formula for BurningShip {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let (x, y) = (z.re, z.im);
+        Complex::new(x * x - y * y + c.re, 2.0 * (x * y).abs() + c.im)
+    }
+
+    // the imaginary axis points down the screen, so the ship is upright, with its masts on top.
+    fn default_view(&self) -> View {
+        View::new(-2.2, 1.2, -1.4, 0.7, 230, 66)
+    }
+}
+
+/// The Tricorn, or Mandelbar set: conj(z)² + c.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tricorn;
+
+impl Formula for Tricorn {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let (x, y) = (z.re, z.im);
+        Complex::new(x * x - y * y + c.re, -2.0 * x * y + c.im)
+    }
+
+    fn default_view(&self) -> View {
+        View::new(-2.2, 1.0, -1.4, 1.4, 230, 66)
+    }
+}
+
+/// The Celtic set: |x² - y²| + i2xy + c. The real part of z² is folded.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Celtic;
+
+impl Formula for Celtic {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let (x, y) = (z.re, z.im);
+        Complex::new((x * x - y * y).abs() + c.re, 2.0 * x * y + c.im)
+    }
+
+    fn default_view(&self) -> View {
+        View::new(-2.2, 0.6, -1.7, 1.7, 230, 66)
+    }
+}
+
+/// The Buffalo: |x² - y²| - i2|xy| + c. Both parts of z² are folded.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Buffalo;
+
+impl Formula for Buffalo {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let (x, y) = (z.re, z.im);
+        Complex::new((x * x - y * y).abs() + c.re, -2.0 * (x * y).abs() + c.im)
+    }
+
+    // folding the imaginary part pushes the set down the screen, unlike the symmetric variants.
+    fn default_view(&self) -> View {
+        View::new(-2.2, 0.6, -0.7, 1.8, 230, 66)
+    }
+}
+
+/// The Perpendicular Mandelbrot: (x² - y²) - i2|x|y + c. Only x is folded.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Perpendicular;
+
+impl Formula for Perpendicular {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let (x, y) = (z.re, z.im);
+        Complex::new(x * x - y * y + c.re, -2.0 * x.abs() * y + c.im)
+    }
+
+    fn default_view(&self) -> View {
+        View::new(-2.2, 0.9, -1.2, 1.2, 230, 66)
+    }
+}
+
+/// One of the formulas above, chosen at runtime, or one typed in.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Fractal {
+    #[default]
+    Mandelbrot,
+    /// z^d + c, with the power d.
+    Multibrot(f64),
+    BurningShip,
+    Tricorn,
+    Celtic,
+    Buffalo,
+    Perpendicular,
+    /// A formula typed in, such as `z^3 - z + c`. Its [`Formula::default_bailout`] is the plain
+    /// |z| > 2 of [`Bailout::default`], since nothing is known about its orbits.
+    Custom(Arc<Expression>),
+}
+
+impl Fractal {
+    /// Every built-in fractal, with the Multibrot set at power 3.
+    pub const ALL: [Fractal; 7] = [
+        Fractal::Mandelbrot,
+        Fractal::Multibrot(3.0),
+        Fractal::BurningShip,
+        Fractal::Tricorn,
+        Fractal::Celtic,
+        Fractal::Buffalo,
+        Fractal::Perpendicular,
+    ];
+
+    /// The name of a built-in fractal, or the text of a custom one.
+    pub fn name(&self) -> &str {
+        match self {
+            Fractal::Mandelbrot => "mandelbrot",
+            Fractal::Multibrot(_) => "multibrot",
+            Fractal::BurningShip => "burning-ship",
+            Fractal::Tricorn => "tricorn",
+            Fractal::Celtic => "celtic",
+            Fractal::Buffalo => "buffalo",
+            Fractal::Perpendicular => "perpendicular",
+            Fractal::Custom(expression) => expression.source(),
+        }
+    }
+
+    /// The fractal with this name. The Multibrot set comes with power 3, see
+    /// [`Fractal::with_power`] for the others.
+    pub fn from_name(name: &str) -> Option<Fractal> {
+        Fractal::ALL
+            .into_iter()
+            .find(|fractal| fractal.name() == name)
+    }
+
+    /// The fractal with this name, or else the one of this formula, as in
+    /// [`Expression::parse`].
+    pub fn parse(text: &str) -> Result<Fractal, String> {
+        if let Some(fractal) = Fractal::from_name(text) {
+            return Ok(fractal);
+        }
+        match Expression::parse(text) {
+            Ok(expression) => Ok(Fractal::Custom(Arc::new(expression))),
+            // a single word was most likely meant as a name.
+            Err(_) if text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+                let names: Vec<&str> = Fractal::ALL.iter().map(Fractal::name).collect();
+                Err(format!(
+                    "unknown formula '{}' (one of: {}, or an expression such as 'z^3 - z + c')",
+                    text,
+                    names.join(", ")
+                ))
+            }
+            Err(error) => Err(format!("invalid formula '{}': {}", text, error)),
+        }
+    }
+
+    /// The power of a Multibrot set, or `None` for the other fractals.
+    pub fn power(&self) -> Option<f64> {
+        match *self {
+            Fractal::Multibrot(power) => Some(power),
+            _ => None,
+        }
+    }
+
+    /// The Multibrot set with the power `power`. Only powers greater than 1 make a fractal.
+    pub fn with_power(power: f64) -> Result<Fractal, String> {
+        if !(power.is_finite() && power > 1.0) {
+            return Err(format!("the power must be greater than 1, not {}", power));
+        }
+        Ok(Fractal::Multibrot(power))
+    }
+}
+
+// Fractal itself is a formula too, for when the choice only has to be made once. The engine
+// matches on it once per point instead, so the loop is compiled for every formula on its own.
+impl Formula for Fractal {
+    fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        match *self {
+            Fractal::Mandelbrot => Mandelbrot.step(z, c),
+            Fractal::Multibrot(power) => Multibrot { power }.step(z, c),
+            Fractal::BurningShip => BurningShip.step(z, c),
+            Fractal::Tricorn => Tricorn.step(z, c),
+            Fractal::Celtic => Celtic.step(z, c),
+            Fractal::Buffalo => Buffalo.step(z, c),
+            Fractal::Perpendicular => Perpendicular.step(z, c),
+            Fractal::Custom(ref expression) => expression.step(z, c),
+        }
+    }
+
+    fn step_at(
+        &self,
+        z: Complex<f64>,
+        c: Complex<f64>,
+        pixel: Complex<f64>,
+        iteration: usize,
+    ) -> Complex<f64> {
+        match self {
+            Fractal::Custom(expression) => expression.step_at(z, c, pixel, iteration),
+            _ => self.step(z, c),
+        }
+    }
+
+    fn start(&self, z: Complex<f64>, c: Complex<f64>, pixel: Complex<f64>) -> Complex<f64> {
+        match self {
+            Fractal::Custom(expression) => expression.start(z, c, pixel),
+            _ => z,
+        }
+    }
+
+    fn degree(&self) -> f64 {
+        match self {
+            Fractal::Custom(expression) => expression.degree(),
+            _ => self.power().unwrap_or(2.0),
+        }
+    }
+
+    fn default_view(&self) -> View {
+        match *self {
+            Fractal::Mandelbrot => Mandelbrot.default_view(),
+            Fractal::Multibrot(power) => Multibrot { power }.default_view(),
+            Fractal::BurningShip => BurningShip.default_view(),
+            Fractal::Tricorn => Tricorn.default_view(),
+            Fractal::Celtic => Celtic.default_view(),
+            Fractal::Buffalo => Buffalo.default_view(),
+            Fractal::Perpendicular => Perpendicular.default_view(),
+            Fractal::Custom(ref expression) => expression.default_view(),
+        }
+    }
+
+    // only the Multibrot sets need a wider radius. The folded variants escape past 2 as z² + c
+    // does, and a typed-in formula gets the plain default too, as nothing is known about its
+    // orbits. --bailout widens it where that is not enough.
+    fn default_bailout(&self) -> Bailout {
+        match *self {
+            Fractal::Multibrot(power) => Multibrot { power }.default_bailout(),
+            _ => Bailout::default(),
+        }
+    }
+
+    fn has_mandelbrot_interior(&self) -> bool {
+        *self == Fractal::Mandelbrot
+    }
+
+    fn uses_iteration(&self) -> bool {
+        match self {
+            Fractal::Custom(expression) => expression.uses_iteration(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,328 @@
+/*
+Turns the bytes read from a terminal in raw mode into key presses and mouse events.
+
+Most keys arrive as the character they type. The arrow keys arrive as escape sequences:
+ESC [ A (or ESC O A, in the terminal's "application" mode) for up, with B, C and D for
+down, right and left. An ESC byte that is not followed by one of those is the escape key itself.
+
+With mouse reporting turned on (see MOUSE_ON), the terminal sends mouse events in xterm's SGR
+format: ESC [ < button ; column ; row, ended by M for a press or m for a release.
+The column and row count from 1. The button number holds several things at once:
+
+  bits 0-1  the button: 0 left, 1 middle, 2 right
+  bit  5    (+32) the mouse moved while the button was held down
+  bit  6    (+64) the wheel, with bits 0-1 telling which way: 64 is up, 65 is down
+  bits 2-4  shift, alt and ctrl, which are ignored here
+*/
+
+/// Asks the terminal to report button presses, movement while a button is held (for dragging),
+/// and to do so in the SGR format, which has no limit on the column and row.
+pub const MOUSE_ON: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1006h";
+pub const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1002l\x1b[?1000l";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Escape,
+    Mouse(Mouse),
+}
+
+/// A mouse event, at a character cell counted from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub action: MouseAction,
+    pub column: usize,
+    pub row: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    Press(MouseButton),
+    Drag(MouseButton),
+    Release(MouseButton),
+    WheelUp,
+    WheelDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+// Ctrl-C, which raw mode delivers as a plain byte.
+pub const CTRL_C: char = '\u{3}';
+
+// the longest sequence worth waiting for the rest of. Anything longer is not one of the
+// sequences above, so it is dropped instead of being held on to forever.
+const MAX_PENDING: usize = 64;
+
+/*
+A read can end in the middle of an escape sequence or a UTF-8 character, when the terminal's
+write is split, or a lot of input arrives at once. The start is held on to until the next read
+completes it, so it is not taken for an escape key followed by text.
+
+A lone ESC at the end of a read is the escape key itself though, since the terminal sends the
+whole of a sequence at once, and waiting for more would leave the key unanswered.
+*/
+#[derive(Debug, Default)]
+pub struct InputParser {
+    pending: Vec<u8>,
+}
+
+impl InputParser {
+    /// The inputs in `bytes`, after whatever was left over from the previous read.
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<Input> {
+        self.pending.extend_from_slice(bytes);
+        let mut inputs = Vec::new();
+        let mut used = 0;
+        while used < self.pending.len() {
+            match parse_one(&self.pending[used..]) {
+                Some((input, length)) => {
+                    inputs.extend(input);
+                    used += length;
+                }
+                None => break,
+            }
+        }
+        self.pending.drain(..used);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.clear();
+        }
+        inputs
+    }
+}
+
+/*
+Parses the input at the start of `bytes`. Returns the input, or None for something that is not
+understood, and how many bytes it used; or None if it goes on past the end of `bytes`.
+*/
+fn parse_one(bytes: &[u8]) -> Option<(Option<Input>, usize)> {
+    let first = *bytes.first()?;
+    if first == 0x1b {
+        return parse_escape(bytes);
+    }
+
+    // anything else is text, possibly a multi-byte UTF-8 character.
+    let length = match first {
+        0xF8..=0xFF => 1,
+        0xF0..=0xF7 => 4,
+        0xE0..=0xEF => 3,
+        0xC0..=0xDF => 2,
+        _ => 1,
+    };
+    let character = bytes.get(..length)?;
+    let input = std::str::from_utf8(character)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .map(Input::Char);
+    Some((input, length))
+}
+
+// the end of a control sequence: the first byte from @ to ~ after the start.
+fn final_byte(bytes: &[u8], start: usize) -> Option<usize> {
+    bytes
+        .get(start..)?
+        .iter()
+        .position(|byte| (0x40..=0x7E).contains(byte))
+        .map(|end| start + end)
+}
+
+// parses the escape sequence at the start of `bytes`, as parse_one does.
+fn parse_escape(bytes: &[u8]) -> Option<(Option<Input>, usize)> {
+    if bytes.starts_with(b"\x1b[<") {
+        return parse_sgr_mouse(bytes);
+    }
+    match bytes {
+        [_, b'[' | b'O'] => None,
+        [_, b'[' | b'O', last, ..] => {
+            let arrow = match last {
+                b'A' => Some(Input::Up),
+                b'B' => Some(Input::Down),
+                b'C' => Some(Input::Right),
+                b'D' => Some(Input::Left),
+                _ => None,
+            };
+            match arrow {
+                Some(arrow) => Some((Some(arrow), 3)),
+                // some other sequence (a function key, say): skip it up to its final byte.
+                None => final_byte(bytes, 2).map(|end| (None, end + 1)),
+            }
+        }
+        _ => Some((Some(Input::Escape), 1)),
+    }
+}
+
+// parses ESC [ < button ; column ; row (M or m), as described at the top.
+fn parse_sgr_mouse(bytes: &[u8]) -> Option<(Option<Input>, usize)> {
+    let end = final_byte(bytes, 3)?;
+    let length = end + 1;
+    if bytes[end] != b'M' && bytes[end] != b'm' {
+        return Some((None, length));
+    }
+    let numbers: Vec<usize> = match std::str::from_utf8(&bytes[3..end]) {
+        Ok(text) => match text.split(';').map(str::parse).collect() {
+            Ok(numbers) => numbers,
+            Err(_) => return Some((None, length)),
+        },
+        Err(_) => return Some((None, length)),
+    };
+    let (code, column, row) = match numbers[..] {
+        [code, column, row] if column > 0 && row > 0 => (code, column - 1, row - 1),
+        _ => return Some((None, length)),
+    };
+
+    let pressed = bytes[end] == b'M';
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        _ => return Some((None, length)),
+    };
+    let action = if code & 64 != 0 {
+        match (pressed, code & 0b11) {
+            (true, 0) => MouseAction::WheelUp,
+            (true, 1) => MouseAction::WheelDown,
+            // the wheel has no releases, and 66 and 67 are sideways scrolling.
+            _ => return Some((None, length)),
+        }
+    } else if code & 32 != 0 {
+        MouseAction::Drag(button)
+    } else if pressed {
+        MouseAction::Press(button)
+    } else {
+        MouseAction::Release(button)
+    };
+    Some((
+        Some(Input::Mouse(Mouse {
+            action,
+            column,
+            row,
+        })),
+        length,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<Input> {
+        InputParser::default().parse(bytes)
+    }
+
+    fn mouse(action: MouseAction, column: usize, row: usize) -> Input {
+        Input::Mouse(Mouse {
+            action,
+            column,
+            row,
+        })
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            parse("aé€😀".as_bytes()),
+            [
+                Input::Char('a'),
+                Input::Char('é'),
+                Input::Char('€'),
+                Input::Char('😀')
+            ]
+        );
+        assert_eq!(
+            parse(b"\x1b[A\x1b[B\x1bOC\x1bOD"),
+            [Input::Up, Input::Down, Input::Right, Input::Left]
+        );
+        assert_eq!(parse(b"\x1b"), [Input::Escape]);
+        assert_eq!(parse(b"\x1bq"), [Input::Escape, Input::Char('q')]);
+        // F5 is skipped whole, and so are bytes that are not UTF-8.
+        assert_eq!(parse(b"\x1b[15~x"), [Input::Char('x')]);
+        assert_eq!(parse(b"\xff\x80y"), [Input::Char('y')]);
+    }
+
+    #[test]
+    fn mouse_events() {
+        use MouseButton::*;
+        for (sequence, action) in [
+            (&b"\x1b[<0;5;3M"[..], MouseAction::Press(Left)),
+            (b"\x1b[<1;5;3M", MouseAction::Press(Middle)),
+            (b"\x1b[<2;5;3M", MouseAction::Press(Right)),
+            (b"\x1b[<0;5;3m", MouseAction::Release(Left)),
+            (b"\x1b[<2;5;3m", MouseAction::Release(Right)),
+            (b"\x1b[<32;5;3M", MouseAction::Drag(Left)),
+            (b"\x1b[<34;5;3M", MouseAction::Drag(Right)),
+            (b"\x1b[<64;5;3M", MouseAction::WheelUp),
+            (b"\x1b[<65;5;3M", MouseAction::WheelDown),
+            // shift (4), alt (8) and ctrl (16) change nothing.
+            (b"\x1b[<4;5;3M", MouseAction::Press(Left)),
+            (b"\x1b[<24;5;3m", MouseAction::Release(Left)),
+            (b"\x1b[<48;5;3M", MouseAction::Drag(Left)),
+            (b"\x1b[<80;5;3M", MouseAction::WheelUp),
+        ] {
+            assert_eq!(parse(sequence), [mouse(action, 4, 2)], "{:?}", sequence);
+        }
+        assert_eq!(
+            parse(b"\x1b[<0;1000;2000M"),
+            [mouse(MouseAction::Press(Left), 999, 1999)]
+        );
+    }
+
+    #[test]
+    fn malformed_mouse_events() {
+        for sequence in [
+            &b"\x1b[<0;0;3M"[..],
+            b"\x1b[<0;5M",
+            b"\x1b[<0;5;3;1M",
+            b"\x1b[<0:5;3M",
+            b"\x1b[<0;5;-3M",
+            b"\x1b[<3;5;3M",
+            b"\x1b[<64;5;3m",
+            b"\x1b[<66;5;3M",
+            b"\x1b[<0;5;3X",
+        ] {
+            // skipped whole, without taking the key after it along.
+            assert_eq!(parse(&[sequence, b"k"].concat()), [Input::Char('k')]);
+        }
+    }
+
+    #[test]
+    fn sequences_split_across_reads() {
+        let mut parser = InputParser::default();
+        assert_eq!(parser.parse(b"a\x1b["), [Input::Char('a')]);
+        assert_eq!(parser.parse(b"A"), [Input::Up]);
+
+        let sequence = b"\x1b[<0;12;7M";
+        for split in 1..sequence.len() {
+            let mut parser = InputParser::default();
+            let mut inputs = parser.parse(&sequence[..split]);
+            inputs.extend(parser.parse(&sequence[split..]));
+            if split == 1 {
+                // a lone ESC at the end of a read is the escape key.
+                assert_eq!(inputs[0], Input::Escape);
+            } else {
+                assert_eq!(
+                    inputs,
+                    [mouse(MouseAction::Press(MouseButton::Left), 11, 6)]
+                );
+            }
+        }
+
+        let mut parser = InputParser::default();
+        assert!(parser.parse(&"é".as_bytes()[..1]).is_empty());
+        assert_eq!(parser.parse(&"é".as_bytes()[1..]), [Input::Char('é')]);
+    }
+
+    #[test]
+    fn endless_sequences_are_dropped() {
+        let mut parser = InputParser::default();
+        assert!(parser.parse(b"\x1b[<").is_empty());
+        assert!(parser.parse(&[b'1'; 100]).is_empty());
+        assert_eq!(parser.parse(b"z"), [Input::Char('z')]);
+    }
+}
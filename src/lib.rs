@@ -0,0 +1,51 @@
+//! The fractal engine behind the `rusty-mandelbrot` binary.
+//!
+//! The work is split in two steps: [`calculate_mandelbrot`] turns a [`Config`] into a grid of
+//! iteration counts, one `usize` per pixel, and the modules in [`render`] turn that grid into
+//! something to look at.
+//!
+//! ```
+//! use rusty_mandelbrot::render::ascii::Ramp;
+//! use rusty_mandelbrot::{calculate_mandelbrot, render, Config, Scale, View};
+//!
+//! let config = Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 60, 20), 100);
+//! let mandelbrot_points = calculate_mandelbrot(&config);
+//!
+//! let mut out = Vec::new();
+//! render::ascii::render_mandelbrot(&mandelbrot_points, 100, &Ramp::default(), Scale::Logarithmic, &mut out)
+//!     .unwrap();
+//! assert_eq!(String::from_utf8(out).unwrap().lines().count(), 20);
+//! ```
+
+pub mod bailout;
+pub mod bookmarks;
+pub mod buddhabrot;
+pub mod expression;
+pub mod formula;
+pub mod location;
+pub mod mandelbrot;
+pub mod newton;
+pub mod palette;
+pub mod parallel;
+pub mod presets;
+pub mod render;
+pub mod scale;
+pub mod scene;
+pub mod terminal;
+pub mod view;
+
+pub use bailout::{Bailout, Norm};
+pub use buddhabrot::{calculate_buddhabrot, Buddhabrot, Density};
+pub use expression::Expression;
+pub use formula::{Formula, Fractal};
+pub use location::Location;
+pub use mandelbrot::{
+    calculate_escapes, calculate_mandelbrot, calculate_mandelbrot_parallel,
+    calculate_mandelbrot_smooth, calculate_mandelbrot_smooth_parallel, escape_time,
+    is_in_cardioid_or_bulb, num_of_mandelbrot_iters_before_escape, smooth_iters_before_escape,
+    Escape, EscapeValue,
+};
+pub use newton::{calculate_newton, Convergence, Newton};
+pub use palette::Palette;
+pub use scale::Scale;
+pub use view::{Config, View};
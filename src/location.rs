@@ -0,0 +1,228 @@
+//! Places in the set that can be saved, shared and returned to: where the view is centered, how
+//! far it is zoomed in, and how it is drawn, independent of the size of the screen.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use num::complex::Complex;
+
+use crate::formula::Fractal;
+use crate::palette::Palette;
+use crate::view::View;
+
+/// A place in the set, and how it is drawn.
+///
+/// Unlike [`View`], which holds the exact bounds for a given number of pixels, a location fits
+/// any screen: [`Location::view`] picks the bounds for the size and shape of the output.
+///
+/// It is written out as a single line, and read back with [`str::parse`]:
+///
+/// ```
+/// use rusty_mandelbrot::location::Location;
+///
+/// let location: Location = "center=-0.75,0.1 zoom=20 iterations=2000 palette=fire".parse().unwrap();
+/// assert_eq!(location.to_string().parse::<Location>().unwrap(), location);
+///
+/// // the Julia set of the point above, seen whole.
+/// let julia: Location = "center=0,0 julia=-0.75,0.1".parse().unwrap();
+/// assert_eq!(julia.julia, Some(location.center));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub center: Complex<f64>,
+    /// Zoom 1 fits the whole set, zoom 2 shows half as much of it, and so on.
+    pub zoom: f64,
+    pub max_iterations: usize,
+    pub palette: Palette,
+    /// The constant of the Julia set shown here, or `None` for the Mandelbrot set.
+    pub julia: Option<Complex<f64>>,
+    /// The equation that is iterated, z² + c unless one of its variants is chosen.
+    pub formula: Fractal,
+}
+
+impl Location {
+    /// The view of this location on a grid of `width` by `height` points, each `point_aspect`
+    /// times as tall as it is wide (see [`View::from_center_zoom_aspect`]).
+    pub fn view(&self, width: usize, height: usize, point_aspect: f64) -> View {
+        View::from_center_zoom_aspect(self.center, self.zoom, width, height, point_aspect)
+    }
+
+    /// The location that a view shows, so that bounds given as min/max can be moved around.
+    ///
+    /// This undoes [`View::from_center_zoom_aspect`], where the zoom is chosen so that the
+    /// default view just fits along one axis: the one where it is the tightest.
+    pub fn from_view(view: &View, max_iterations: usize, palette: Palette) -> Location {
+        let default = View::default();
+        let center = Complex::new(
+            (view.real_min + view.real_max) / 2.0,
+            (view.imaginary_min + view.imaginary_max) / 2.0,
+        );
+        let zoom = f64::max(
+            (default.real_max - default.real_min) / (view.real_max - view.real_min),
+            (default.imaginary_max - default.imaginary_min)
+                / (view.imaginary_max - view.imaginary_min),
+        );
+        Location {
+            center,
+            zoom,
+            max_iterations,
+            palette,
+            julia: None,
+            formula: Fractal::Mandelbrot,
+        }
+    }
+
+    /// The location that shows all of `view`, whatever the shape of the output: like
+    /// [`Location::from_view`], but fitted along the axis where the view is the widest instead.
+    ///
+    /// Where the view has the 3:2 shape of [`View::default`], both are the same.
+    pub fn fitting(view: &View, max_iterations: usize, palette: Palette) -> Location {
+        let default = View::default();
+        let zoom = f64::min(
+            (default.real_max - default.real_min) / (view.real_max - view.real_min),
+            (default.imaginary_max - default.imaginary_min)
+                / (view.imaginary_max - view.imaginary_min),
+        );
+        Location {
+            zoom,
+            ..Location::from_view(view, max_iterations, palette)
+        }
+    }
+}
+
+impl Default for Location {
+    /// The whole set, as [`View::default`] shows it.
+    fn default() -> Location {
+        Location::from_view(&View::default(), 1000, Palette::default())
+    }
+}
+
+impl fmt::Display for Location {
+    // f64's Display prints the shortest form that parses back to exactly the same number,
+    // so nothing is lost on the way to a file and back, however deep the zoom.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "center={},{} zoom={} iterations={} palette={}",
+            self.center.re,
+            self.center.im,
+            self.zoom,
+            self.max_iterations,
+            self.palette.name()
+        )?;
+        // left out for the Mandelbrot set, so those lines read as they always have.
+        if let Some(c) = self.julia {
+            write!(f, " julia={},{}", c.re, c.im)?;
+        }
+        // a formula that was typed in loses its spaces, which separate the pairs here.
+        if self.formula != Fractal::Mandelbrot {
+            let name: String = self.formula.name().split_whitespace().collect();
+            write!(f, " formula={}", name)?;
+        }
+        if let Some(power) = self.formula.power() {
+            write!(f, " power={}", power)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    /// Parses `key=value` pairs separated by spaces, as written by `Display`. Only `center` is
+    /// required, the others default to those of [`Location::default`], and `julia` and
+    /// `formula` to the Mandelbrot set. `power` only goes with `formula=multibrot`.
+    fn from_str(text: &str) -> Result<Location, String> {
+        let mut location = Location::default();
+        let mut has_center = false;
+        let mut power = None;
+        for pair in text.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, found '{}'", pair))?;
+            let invalid = || format!("invalid {} '{}'", key, value);
+            match key {
+                "center" => {
+                    location.center = parse_complex(value).ok_or_else(invalid)?;
+                    has_center = true;
+                }
+                "julia" => location.julia = Some(parse_complex(value).ok_or_else(invalid)?),
+                "formula" => location.formula = Fractal::parse(value)?,
+                "power" => power = Some(parse_finite(value).ok_or_else(invalid)?),
+                "zoom" => {
+                    location.zoom = parse_finite(value)
+                        .filter(|&zoom| zoom > 0.0)
+                        .ok_or_else(invalid)?
+                }
+                "iterations" => {
+                    location.max_iterations = value
+                        .parse()
+                        .ok()
+                        .filter(|&iterations| iterations > 0)
+                        .ok_or_else(invalid)?
+                }
+                "palette" => location.palette = Palette::from_name(value).ok_or_else(invalid)?,
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+        if !has_center {
+            return Err("a location needs a center".to_string());
+        }
+        match (&location.formula, power) {
+            (Fractal::Multibrot(_), Some(power)) => location.formula = Fractal::with_power(power)?,
+            (_, Some(_)) => return Err("power only goes with formula=multibrot".to_string()),
+            _ => {}
+        }
+        Ok(location)
+    }
+}
+
+// "re,im", as in center=-0.75,0.1.
+fn parse_complex(text: &str) -> Option<Complex<f64>> {
+    let (re, im) = text.split_once(',')?;
+    Some(Complex::new(parse_finite(re)?, parse_finite(im)?))
+}
+
+fn parse_finite(text: &str) -> Option<f64> {
+    text.trim()
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite())
+}
+
+/// The locations visited so far, to step back and forward through like a web browser's history.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    back: VecDeque<Location>,
+    forward: Vec<Location>,
+}
+
+impl History {
+    /// The most steps that are remembered. The oldest ones are forgotten first.
+    pub const LIMIT: usize = 1000;
+
+    /// Remembers `current` before moving away from it. Anything that was undone is forgotten,
+    /// since it no longer follows on from here.
+    pub fn push(&mut self, current: Location) {
+        if self.back.len() == History::LIMIT {
+            self.back.pop_front();
+        }
+        self.back.push_back(current);
+        self.forward.clear();
+    }
+
+    /// Steps back from `current`, or returns `None` if there is nothing to go back to.
+    pub fn undo(&mut self, current: Location) -> Option<Location> {
+        let previous = self.back.pop_back()?;
+        self.forward.push(current);
+        Some(previous)
+    }
+
+    /// Steps forward again after [`History::undo`], or returns `None` if nothing was undone.
+    pub fn redo(&mut self, current: Location) -> Option<Location> {
+        let next = self.forward.pop()?;
+        self.back.push_back(current);
+        Some(next)
+    }
+}
@@ -0,0 +1,453 @@
+use num::complex::Complex;
+
+use crate::bailout::Bailout;
+use crate::formula::{
+    Buffalo, BurningShip, Celtic, Formula, Fractal, Mandelbrot, Multibrot, Perpendicular, Tricorn,
+};
+use crate::palette::Palette;
+use crate::parallel;
+use crate::view::Config;
+
+/// Computes the number of iterations before escape for every pixel of `config.view`.
+///
+/// The grid is indexed as `mandelbrot_points[pixel_y][pixel_x]`. Pixels that never escaped hold
+/// `config.max_iterations`. With [`Config::julia`] set, this is the grid of that Julia set
+/// instead, as are the grids of all the other `calculate_` functions.
+pub fn calculate_mandelbrot(config: &Config) -> Vec<Vec<usize>> {
+    // loop through each y-axis coordinate, and collect each row into a vec of all the rows.
+    (0..config.view.height)
+        .map(|pixel_y| calculate_mandelbrot_row(config, pixel_y))
+        .collect()
+}
+
+/// Same as [`calculate_mandelbrot`], but spread over `threads` threads.
+///
+/// The result is identical to the serial version for any number of threads: every row is still
+/// computed by [`calculate_mandelbrot_row`], only on another thread.
+pub fn calculate_mandelbrot_parallel(config: &Config, threads: usize) -> Vec<Vec<usize>> {
+    parallel::map_rows(config.view.height, threads, |pixel_y| {
+        calculate_mandelbrot_row(config, pixel_y)
+    })
+}
+
+/// Computes a single row of the grid returned by [`calculate_mandelbrot`].
+pub fn calculate_mandelbrot_row(config: &Config, pixel_y: usize) -> Vec<usize> {
+    calculate_row_with(config, pixel_y, |c, z| {
+        num_of_mandelbrot_iters_before_escape(c, z, config)
+    })
+}
+
+/// Same as [`calculate_mandelbrot`], but with the fractional escape count from
+/// [`smooth_iters_before_escape`] for every pixel. Pixels that never escaped hold
+/// `config.max_iterations` as a float.
+pub fn calculate_mandelbrot_smooth(config: &Config) -> Vec<Vec<f64>> {
+    (0..config.view.height)
+        .map(|pixel_y| calculate_mandelbrot_smooth_row(config, pixel_y))
+        .collect()
+}
+
+/// Same as [`calculate_mandelbrot_smooth`], but spread over `threads` threads.
+pub fn calculate_mandelbrot_smooth_parallel(config: &Config, threads: usize) -> Vec<Vec<f64>> {
+    parallel::map_rows(config.view.height, threads, |pixel_y| {
+        calculate_mandelbrot_smooth_row(config, pixel_y)
+    })
+}
+
+/// Computes a single row of the grid returned by [`calculate_mandelbrot_smooth`].
+pub fn calculate_mandelbrot_smooth_row(config: &Config, pixel_y: usize) -> Vec<f64> {
+    calculate_row_with(config, pixel_y, |c, z| {
+        smooth_iters_before_escape(c, z, config)
+    })
+}
+
+/// Like [`calculate_mandelbrot`], but keeps the whole [`Escape`] for every pixel, including the
+/// period of the points inside the set. Spread over `threads` threads.
+pub fn calculate_escapes(config: &Config, threads: usize) -> Vec<Vec<Escape>> {
+    parallel::map_rows(config.view.height, threads, |pixel_y| {
+        calculate_row_with(config, pixel_y, |c, z| escape_time(c, z, config))
+    })
+}
+
+// runs `escape` for every pixel of a row, with the pixel as c and z starting at zero,
+// or for a Julia set, the other way around.
+fn calculate_row_with<T, F>(config: &Config, pixel_y: usize, escape: F) -> Vec<T>
+where
+    F: Fn(Complex<f64>, Complex<f64>) -> T,
+{
+    let view = &config.view;
+    let mut row: Vec<T> = Vec::with_capacity(view.width);
+
+    // loop through each x-axis coordinate.
+    // Now that we have both x and y coordinates, we have a point, or a pixel.
+    for pixel_x in 0..view.width {
+        // the current point - the pixel coordinate - converted into a complex number.
+        // See View::pixel_to_complex for how the pixel is placed on the complex plane.
+        let point = view.pixel_to_complex(pixel_x, pixel_y);
+
+        // for the Mandelbrot set, c is the point, and z is the starting point of the
+        // Mandelbrot, "in the middle" so to speak.
+        // A Julia set swaps the roles: c is the same for every point, and z starts at the point.
+        // The same equation is iterated either way, so each Julia set is a slice through the
+        // same family of orbits, and the Mandelbrot set is the map of which ones are connected.
+        let (c, z) = match config.julia {
+            None => (point, Complex { re: 0.0, im: 0.0 }),
+            Some(c) => (c, point),
+        };
+
+        // We now have what we need to calculate the Mandelbrot set equation:
+        //   z * z + c
+        let escaped_at = escape(c, z);
+
+        // push the number of iterations the point took, into the row vec.
+        row.push(escaped_at);
+    }
+    row
+}
+
+/*
+Given a point in space (x, y), returns 'max_iterations' if point
+belongs to the Mandelbrot set, else returns the number of iterations
+before point escaped. (Escape value: |z| > 2.0)
+
+Example:
+  x = 0.40
+  y = 0.91
+  c_complex_number = (x+y)
+  z_complex_number = (0+0)
+
+  ////
+  // FIRST ITERATION:
+  // Mandelbrot equation: z * z + c
+  // z_complex_number * z_complex_number + c_complex_number
+  z = (0+0) * (0+0) + (0.40+0.91) = (0.40+0.91)
+
+  ////
+  // SECOND ITERATION:
+  // c is still    (0.4+0.91)
+  // z is now also (0.4+0.91)
+  // formula for multiplying two complex numbers = (a+b) * (c+d) = ((ac-bd)+(ad+bc))
+
+  (0.40 + 0.91) * (0.40 + 0.91) + (0.40 + 0.91) = ((0.4*0.4)-(0.92*0.92)) + ((0.4*0.92)+(0.92*0.4)) + (0.40 + 0.92)
+                                                    (0.16 - 0.84 = -0.68) + (0.365 + 0.365 = 0.73)
+                                (add 'c')             −0,68 + 0.40        +        0.73 + 0.92
+                                (new value of 'z')        -0.28           +            1.65
+                                (the resulting complex number)     (-0.28, i1.65)
+
+  // new value of z after calculation
+  z = (-0.28+1.65)
+
+  ////
+  // THIRD ITERATION:
+  // c is still    (0.4+0.91)
+  // z is now      (-0.28+1.65)
+
+  // Next value of z
+  (-0.28+1.65) * (-0.28+1.65) + (0.4+0.91) = (-2.24-0.00)
+
+  // New value of z is now (-2.24 -0.00). Since z is more than 2.0 away
+  // from the starting point (origo), it means that z will escape into infinity
+  // if we keep iterating it, so it does not belong to the Mandelbrot set. When z escapes, we
+  // stop the loop and return the number of iterations it took up until it exceeded the escape value.
+
+  number_of_iterations_before_escape = 3
+
+  // since the number of iterations were 3 in this particular case,
+  // we return the number 3, which we display as '.' in the final image.
+
+  return number_of_iterations_before_escape
+
+*/
+/// Returns `config.max_iterations` if the point `c` belongs to the Mandelbrot set, else the number
+/// of iterations it took `z` to escape past `config.bailout`. Start with `z` at zero for the
+/// Mandelbrot set. `config.view` is not used, and `config.formula` picks the equation, so this
+/// works the same for the Burning Ship and the other variants.
+pub fn num_of_mandelbrot_iters_before_escape(
+    c: Complex<f64>,
+    z: Complex<f64>,
+    config: &Config,
+) -> usize {
+    // when or if z escapes, we count the number of iterations it has made up until that point.
+    // In case z never escapes, we get the cap, which in this case is
+    // the maximum number of iterations. Or else it will just continue forever.
+    escape_time(c, z, config).iterations
+}
+
+/// What happened to a single point: how many iterations it took to escape, or if it never did,
+/// whether its orbit settled into a cycle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Escape {
+    /// The number of iterations before escape, or `max_iterations` if the point never escaped.
+    pub iterations: usize,
+    /// Whether the point escaped. If not, it is considered part of the set.
+    pub escaped: bool,
+    /// The value of z when it escaped, or when the iteration stopped.
+    pub z: Complex<f64>,
+    /// For points that never escaped: the length of the cycle their orbit was caught in, if one
+    /// was found. Points in the main cardioid have period 1, those in the big bulb period 2, and
+    /// every other bulb of the set has its own period.
+    pub period: Option<usize>,
+}
+
+impl Escape {
+    fn escaped(iterations: usize, z: Complex<f64>) -> Escape {
+        Escape {
+            iterations,
+            escaped: true,
+            z,
+            period: None,
+        }
+    }
+
+    fn interior(max_iterations: usize, z: Complex<f64>, period: Option<usize>) -> Escape {
+        Escape {
+            iterations: max_iterations,
+            escaped: false,
+            z,
+            period,
+        }
+    }
+}
+
+/// Runs the equation of `config.formula` on `z` until it escapes past `config.bailout`, until its
+/// orbit is found to be caught in a cycle, or until `config.max_iterations` is reached.
+pub fn escape_time(c: Complex<f64>, z: Complex<f64>, config: &Config) -> Escape {
+    iterate_fractal(c, z, config, config.bailout)
+}
+
+/// Same as [`escape_time`], with any [`Formula`] instead of `config.formula`, including ones from
+/// outside this crate.
+pub fn escape_time_with<F: Formula>(
+    formula: &F,
+    c: Complex<f64>,
+    z: Complex<f64>,
+    config: &Config,
+) -> Escape {
+    iterate(formula, c, z, config, config.bailout)
+}
+
+// picks the formula once per point, so that the loop in `iterate` is compiled for each formula
+// on its own, and the step inlined into it, as if it was the only one.
+fn iterate_fractal(c: Complex<f64>, z: Complex<f64>, config: &Config, bailout: Bailout) -> Escape {
+    match config.formula {
+        Fractal::Custom(ref expression) => iterate(&**expression, c, z, config, bailout),
+        Fractal::Mandelbrot => iterate(&Mandelbrot, c, z, config, bailout),
+        Fractal::Multibrot(power) => iterate(&Multibrot { power }, c, z, config, bailout),
+        Fractal::BurningShip => iterate(&BurningShip, c, z, config, bailout),
+        Fractal::Tricorn => iterate(&Tricorn, c, z, config, bailout),
+        Fractal::Celtic => iterate(&Celtic, c, z, config, bailout),
+        Fractal::Buffalo => iterate(&Buffalo, c, z, config, bailout),
+        Fractal::Perpendicular => iterate(&Perpendicular, c, z, config, bailout),
+    }
+}
+
+fn iterate<F: Formula>(
+    formula: &F,
+    c: Complex<f64>,
+    mut z: Complex<f64>,
+    config: &Config,
+    bailout: Bailout,
+) -> Escape {
+    // the point of the pixel, for the formulas that use it: c, or the start of a Julia orbit.
+    let pixel = match config.julia {
+        None => c,
+        Some(_) => z,
+    };
+    z = formula.start(z, c, pixel);
+
+    // most of the set, at least when looking at all of it, lies inside the main cardioid or the
+    // big bulb to its left. Points in there are known to never escape, so there is no need to
+    // spend max_iterations finding that out. This only holds when z starts at zero (always
+    // for the Mandelbrot set, and only at the origin of a Julia set, where the orbit is the same),
+    // and when the bailout is wide enough that no orbit of a point in the set passes it.
+    // The other formulas have shapes of their own, so it is only used for z² + c.
+    if config.interior_shortcut
+        && formula.has_mandelbrot_interior()
+        && z == Complex::new(0.0, 0.0)
+        && bailout.contains_set()
+    {
+        if let Some(period) = cardioid_or_bulb_period(c) {
+            return Escape::interior(config.max_iterations, z, Some(period));
+        }
+    }
+
+    /*
+    Points in the set, outside the cardioid and bulb, still run all the way to max_iterations.
+    Most of them are in a bulb of their own, where the orbit is pulled into a cycle:
+    after a while, z keeps returning to the same few values.
+
+    Brent's method spots such a cycle with a single saved value. Every time the number of
+    iterations since the last save reaches a power of two, z is saved. If z ever comes back
+    within the tolerance of the saved value, the orbit is going in circles, and the number of
+    iterations since the save is the length of that circle, its period. Doubling the distance
+    between saves makes sure that even long cycles fit in between two of them eventually.
+    */
+    let tolerance_squared = config.periodicity_tolerance * config.periodicity_tolerance;
+    let periodicity_check = config.periodicity_check && !formula.uses_iteration();
+    let mut saved = z;
+    let mut steps_since_save = 0;
+    let mut steps_until_save = 1;
+
+    // when z gets further than 2 from origo, it is going to speed off into infinity, so
+    // we stop the iteration when it passes the bailout radius (2 unless configured otherwise).
+    // If z never escapes, then z belongs to the Mandelbrot set and we display that pixel
+    // as white-space in the final image.
+    // z is tested before each of the max_iterations steps, and not once more after the last one:
+    // a point that only escaped there would get max_iterations as its count, the count of the
+    // points in the set, so it is counted as one of them, and never escaped with that count.
+    for i in 0..config.max_iterations {
+        if bailout.escaped(z) {
+            return Escape::escaped(i, z);
+        }
+        // the mathematical function for the Mandelbrot set, z * z + c, or one of its variants.
+        z = formula.step_at(z, c, pixel, i);
+
+        if periodicity_check {
+            steps_since_save += 1;
+            if (z - saved).norm_sqr() <= tolerance_squared {
+                return Escape::interior(config.max_iterations, z, Some(steps_since_save));
+            }
+            if steps_since_save == steps_until_save {
+                saved = z;
+                steps_since_save = 0;
+                steps_until_save *= 2;
+            }
+        }
+    }
+    Escape::interior(config.max_iterations, z, None)
+}
+
+/*
+The main cardioid is the heart shaped body of the set, and the period-2 bulb is the round disc
+touching it on the left, centered on -1. Both have a closed form:
+
+  cardioid: q * (q + (x - 1/4)) <= y^2 / 4,  where q = (x - 1/4)^2 + y^2
+  bulb:     (x + 1)^2 + y^2 <= 1/16          (a circle with radius 1/4 around -1)
+*/
+/// Whether `c` lies inside the main cardioid or the period-2 bulb, and so belongs to the set.
+pub fn is_in_cardioid_or_bulb(c: Complex<f64>) -> bool {
+    cardioid_or_bulb_period(c).is_some()
+}
+
+/// 1 if `c` lies inside the main cardioid, 2 if it lies inside the period-2 bulb, else `None`.
+pub fn cardioid_or_bulb_period(c: Complex<f64>) -> Option<usize> {
+    let x_shifted = c.re - 0.25;
+    let y_squared = c.im * c.im;
+    let q = x_shifted * x_shifted + y_squared;
+    if q * (q + x_shifted) <= y_squared / 4.0 {
+        return Some(1);
+    }
+
+    let x_plus_one = c.re + 1.0;
+    if x_plus_one * x_plus_one + y_squared <= 1.0 / 16.0 {
+        return Some(2);
+    }
+    None
+}
+
+/// The smallest bailout radius used by [`smooth_iters_before_escape`].
+///
+/// The log-log formula assumes that |z| is already large when the point escapes. With the classic
+/// radius of 2 the fractional part comes out wrong and faint bands show up again, so the orbit is
+/// followed a few iterations further, until |z| passes 256.
+pub const SMOOTH_BAILOUT_RADIUS: f64 = 256.0;
+
+/*
+The plain iteration count jumps from n to n+1 between neighbouring pixels, which shows up as bands
+of flat colour. The normalized iteration count uses how far past the bailout radius the final z
+ended up, to tell how close the point was to escaping one iteration earlier:
+
+  mu = n + 1 - log2(log2|z|)
+
+Since every iteration roughly squares |z|, log2|z| roughly doubles, and log2 of that grows by
+exactly one per iteration. Subtracting it cancels the jump in n, so mu changes continuously across the bands.
+*/
+/// Returns the fractional number of iterations before `z` escapes, or `max_iterations` as a float
+/// if the point belongs to the Mandelbrot set. Start with `z` at zero for the Mandelbrot set.
+///
+/// The radius of `config.bailout` is raised to [`SMOOTH_BAILOUT_RADIUS`] if it is smaller.
+pub fn smooth_iters_before_escape(c: Complex<f64>, z: Complex<f64>, config: &Config) -> f64 {
+    let bailout = config.bailout.with_min_radius(SMOOTH_BAILOUT_RADIUS);
+    let escape = iterate_fractal(c, z, config, bailout);
+    if escape.escaped {
+        smooth_iteration_count_with_degree(escape.iterations, escape.z, config.formula.degree())
+    } else {
+        config.max_iterations as f64
+    }
+}
+
+/// The normalized iteration count for a point that escaped after `iterations` iterations,
+/// with `z` being its value at that point.
+pub fn smooth_iteration_count(iterations: usize, z: Complex<f64>) -> f64 {
+    smooth_iteration_count_with_degree(iterations, z, 2.0)
+}
+
+/// Same as [`smooth_iteration_count`], for a formula that raises z to the power `degree` in every
+/// step (see [`Formula::degree`]). Then log2|z| grows `degree` times per iteration instead of
+/// doubling, so the logarithm that cancels it out has that base instead of 2.
+pub fn smooth_iteration_count_with_degree(iterations: usize, z: Complex<f64>, degree: f64) -> f64 {
+    // ln|z| is half of ln(|z|^2).
+    let log_modulus = z.norm_sqr().ln() / 2.0;
+    let log2_modulus = log_modulus / std::f64::consts::LN_2;
+    // log2 is exact where dividing by ln(2) may round, so z² + c keeps using it.
+    let nu = if degree == 2.0 {
+        log2_modulus.log2()
+    } else {
+        log2_modulus.ln() / degree.ln()
+    };
+    (iterations as f64 + 1.0 - nu).max(0.0)
+}
+
+/// A value per pixel that came out of the escape-time engine, which the colouring backends can
+/// take either way: the plain iteration count (`usize`) or the smooth count (`f64`).
+pub trait EscapeValue: Copy + Send + Sync + std::fmt::Display {
+    fn to_f64(self) -> f64;
+
+    /// The period of a point inside the set, if known, so it can be coloured by it.
+    fn period(self) -> Option<usize> {
+        None
+    }
+
+    /// The root a point converged to, for a Newton fractal, so it can be coloured by its basin.
+    fn root(self) -> Option<usize> {
+        None
+    }
+
+    /// A colour the value picks for itself, like the brightness of a Buddhabrot, rather than
+    /// one picked by the number of iterations.
+    fn color(self, _palette: Palette) -> Option<[u8; 3]> {
+        None
+    }
+}
+
+impl EscapeValue for usize {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl EscapeValue for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl EscapeValue for Escape {
+    fn to_f64(self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn period(self) -> Option<usize> {
+        self.period
+    }
+}
+
+impl std::fmt::Display for Escape {
+    /// The number of iterations, like a plain `usize` grid, followed by `p` and the period for
+    /// points inside the set that have one.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.period {
+            Some(period) => write!(f, "{}p{}", self.iterations, period),
+            None => write!(f, "{}", self.iterations),
+        }
+    }
+}
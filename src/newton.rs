@@ -0,0 +1,353 @@
+//! Newton fractals: the other classic family, where every point is a guess at a root of a
+//! polynomial, and the picture shows which root Newton's method finds from there, and how fast.
+//!
+//! Instead of escaping past a bailout, the orbits here settle down: once a step moves z by less
+//! than the tolerance, it has converged. The grid comes back in the same form as the one of
+//! [`calculate_mandelbrot`](crate::calculate_mandelbrot), with a [`Convergence`] per pixel.
+//!
+//! ```
+//! use rusty_mandelbrot::newton::{calculate_newton, Newton};
+//! use rusty_mandelbrot::{Config, View};
+//!
+//! // z^3 - 1, whose roots are the three cube roots of 1.
+//! let newton = Newton::from_coefficients(&[1.0.into(), 0.0.into(), 0.0.into(), (-1.0).into()]).unwrap();
+//! let config = Config::new(View::new(-2.0, 2.0, -1.5, 1.5, 40, 30), 100);
+//! let basins = calculate_newton(&config, &newton, 2);
+//!
+//! // the point 1 is a root itself, so the pixel right on it belongs to that root's basin.
+//! let root = basins[15][30].root.unwrap();
+//! assert!((newton.roots()[root] - 1.0).norm() < 1e-6);
+//! ```
+
+use std::fmt;
+
+use num::complex::Complex;
+
+use crate::mandelbrot::EscapeValue;
+use crate::parallel;
+use crate::view::{Config, View};
+
+/// Newton's method for a polynomial, given by its roots: every step moves z to
+/// `z - a·f(z)/f'(z)`, where `a` is the relaxation factor.
+///
+/// The Nova variant adds c to every step, where c is the pixel, and z starts at a root of the
+/// polynomial, much like the Mandelbrot set starts at 0. With [`Config::julia`] set, the pixel is
+/// where z starts and c stays the same instead, as for the Julia sets of the Mandelbrot set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Newton {
+    roots: Vec<Complex<f64>>,
+    // of the polynomial with those roots and a leading coefficient of 1, lowest power first.
+    coefficients: Vec<Complex<f64>>,
+    /// How far along the Newton step each step goes. 1, the default, is Newton's method itself;
+    /// other values bend the basins into spirals and tendrils.
+    pub relaxation: Complex<f64>,
+    /// Add c to every step, for a Nova fractal.
+    pub nova: bool,
+    /// How little a step has to move z for it to count as converged.
+    pub tolerance: f64,
+}
+
+impl Newton {
+    /// Newton's method for the polynomial with these roots. A root that is given twice is a
+    /// double root, where the method converges more slowly.
+    pub fn from_roots(roots: &[Complex<f64>]) -> Result<Newton, String> {
+        if roots.len() < 2 {
+            return Err("a Newton fractal needs a polynomial with at least 2 roots".to_string());
+        }
+        if roots
+            .iter()
+            .any(|root| !(root.re.is_finite() && root.im.is_finite()))
+        {
+            return Err("the roots must be finite numbers".to_string());
+        }
+        // multiply out (z - r1)(z - r2)..., one root at a time.
+        let mut coefficients = vec![Complex::new(1.0, 0.0)];
+        for &root in roots {
+            coefficients.insert(0, Complex::new(0.0, 0.0));
+            for power in 0..coefficients.len() - 1 {
+                let next = coefficients[power + 1];
+                coefficients[power] -= root * next;
+            }
+        }
+        Ok(Newton {
+            roots: roots.to_vec(),
+            coefficients,
+            relaxation: Complex::new(1.0, 0.0),
+            nova: false,
+            tolerance: 1e-6,
+        })
+    }
+
+    /// Newton's method for the polynomial with these coefficients, highest power first, as it
+    /// is written: `[1, 0, 0, -1]` is z³ - 1. Its roots are found numerically.
+    pub fn from_coefficients(coefficients: &[Complex<f64>]) -> Result<Newton, String> {
+        if coefficients
+            .iter()
+            .any(|coefficient| !(coefficient.re.is_finite() && coefficient.im.is_finite()))
+        {
+            return Err("the coefficients must be finite numbers".to_string());
+        }
+        // leading zeros do not change the polynomial, only the look of it.
+        let first = coefficients
+            .iter()
+            .position(|&coefficient| coefficient != Complex::new(0.0, 0.0))
+            .unwrap_or(coefficients.len());
+        let coefficients = &coefficients[first..];
+        if coefficients.len() < 3 {
+            return Err("a Newton fractal needs a polynomial of degree 2 or more".to_string());
+        }
+        Newton::from_roots(&find_roots(coefficients))
+    }
+
+    /// Checks the settings that can be changed after construction.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.relaxation.re.is_finite() && self.relaxation.im.is_finite())
+            || self.relaxation == Complex::new(0.0, 0.0)
+        {
+            return Err("the relaxation must be a finite number other than 0".to_string());
+        }
+        if !(self.tolerance.is_finite() && self.tolerance > 0.0) {
+            return Err("the Newton tolerance must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// The roots of the polynomial. [`Convergence::root`] is an index into these.
+    pub fn roots(&self) -> &[Complex<f64>] {
+        &self.roots
+    }
+
+    /// The view that shows all the roots, with room around them for the basins to meet. For a
+    /// Nova fractal, the view around 0, where its copies of the Mandelbrot set are.
+    pub fn default_view(&self) -> View {
+        if self.nova {
+            return View::new(-1.8, 0.6, -0.8, 0.8, 230, 66);
+        }
+        let center = self.roots.iter().sum::<Complex<f64>>() / self.roots.len() as f64;
+        let radius = self
+            .roots
+            .iter()
+            .map(|root| (root - center).norm())
+            .fold(0.0, f64::max)
+            .max(1e-3);
+        View::new(
+            center.re - 2.25 * radius,
+            center.re + 2.25 * radius,
+            center.im - 1.5 * radius,
+            center.im + 1.5 * radius,
+            230,
+            66,
+        )
+    }
+
+    /// One step of the method from `z`, with `c` added for a Nova fractal.
+    pub fn step(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        // f(z) and f'(z) together, by Horner's method.
+        let mut value = Complex::new(0.0, 0.0);
+        let mut derivative = Complex::new(0.0, 0.0);
+        for &coefficient in self.coefficients.iter().rev() {
+            derivative = derivative * z + value;
+            value = value * z + coefficient;
+        }
+        let next = z - self.relaxation * value / derivative;
+        if self.nova {
+            next + c
+        } else {
+            next
+        }
+    }
+
+    /// Runs the method from `z` until it converges, or for `max_iterations` steps.
+    pub fn converge(
+        &self,
+        c: Complex<f64>,
+        mut z: Complex<f64>,
+        max_iterations: usize,
+    ) -> Convergence {
+        let tolerance_squared = self.tolerance * self.tolerance;
+        for i in 0..max_iterations {
+            let next = self.step(z, c);
+            // a step from where f'(z) = 0 goes off to infinity, and never comes back.
+            if !(next.re.is_finite() && next.im.is_finite()) {
+                break;
+            }
+            if (next - z).norm_sqr() <= tolerance_squared {
+                return Convergence {
+                    iterations: i,
+                    converged: true,
+                    root: self.nearest_root(next),
+                };
+            }
+            z = next;
+        }
+        Convergence {
+            iterations: max_iterations,
+            converged: false,
+            root: None,
+        }
+    }
+
+    // the root that z has converged to. With c added, the points the orbits settle on are not
+    // the roots anymore, so a Nova fractal has no roots to tell apart.
+    fn nearest_root(&self, z: Complex<f64>) -> Option<usize> {
+        if self.nova {
+            return None;
+        }
+        (0..self.roots.len()).min_by(|&a, &b| {
+            let distance = |index: usize| (self.roots[index] - z).norm_sqr();
+            distance(a).total_cmp(&distance(b))
+        })
+    }
+}
+
+impl Default for Newton {
+    /// z³ - 1, the best known Newton fractal, with its three basins around the cube roots of 1.
+    fn default() -> Newton {
+        let height = 3f64.sqrt() / 2.0;
+        let roots = [
+            Complex::new(1.0, 0.0),
+            Complex::new(-0.5, height),
+            Complex::new(-0.5, -height),
+        ];
+        Newton::from_roots(&roots).expect("three roots are enough")
+    }
+}
+
+/*
+The Durand-Kerner method finds all the roots of a polynomial at once. It starts from a guess
+for each, spread out around a circle, and improves them all together: each guess r is moved to
+
+  r - f(r) / ((r - s1)(r - s2)...)
+
+over all the other guesses s, which is Newton's method for f with the other roots divided out.
+For a polynomial with a leading coefficient of 1 and simple roots, the guesses converge to the
+roots from almost any start, and they cannot end up on the same root.
+*/
+fn find_roots(coefficients: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let degree = coefficients.len() - 1;
+    // highest power first, divided by the leading coefficient.
+    let monic: Vec<Complex<f64>> = coefficients
+        .iter()
+        .map(|&coefficient| coefficient / coefficients[0])
+        .collect();
+    let evaluate = |z: Complex<f64>| {
+        monic
+            .iter()
+            .fold(Complex::new(0.0, 0.0), |value, &coefficient| {
+                value * z + coefficient
+            })
+    };
+
+    // powers of a number that is neither real nor on the unit circle, so no two guesses are
+    // the same, and none of them is symmetric to another.
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots: Vec<Complex<f64>> = (0..degree).map(|k| seed.powi(k as i32)).collect();
+    for _ in 0..1000 {
+        let mut largest_change: f64 = 0.0;
+        for index in 0..degree {
+            let root = roots[index];
+            let mut denominator = Complex::new(1.0, 0.0);
+            for (other_index, &other) in roots.iter().enumerate() {
+                if other_index != index {
+                    denominator *= root - other;
+                }
+            }
+            let change = evaluate(root) / denominator;
+            if change.re.is_finite() && change.im.is_finite() {
+                roots[index] = root - change;
+                largest_change = largest_change.max(change.norm());
+            }
+        }
+        if largest_change < 1e-15 {
+            break;
+        }
+    }
+    roots
+}
+
+/// Reads complex numbers separated by spaces, each written as `re,im` or just `re`, like the
+/// roots `"1 -0.5,0.866 -0.5,-0.866"`.
+pub fn parse_numbers(text: &str) -> Result<Vec<Complex<f64>>, String> {
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| format!("invalid number '{}'", part))
+    };
+    text.split_whitespace()
+        .map(|number| match number.split_once(',') {
+            Some((re, im)) => Ok(Complex::new(parse(re)?, parse(im)?)),
+            None => Ok(Complex::new(parse(number)?, 0.0)),
+        })
+        .collect()
+}
+
+/// Writes complex numbers the way [`parse_numbers`] reads them, without losing any digits.
+pub fn format_numbers(numbers: &[Complex<f64>]) -> String {
+    let format = |number: &Complex<f64>| {
+        if number.im == 0.0 {
+            format!("{:?}", number.re)
+        } else {
+            format!("{:?},{:?}", number.re, number.im)
+        }
+    };
+    numbers.iter().map(format).collect::<Vec<_>>().join(" ")
+}
+
+/// What happened to a single point under Newton's method.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convergence {
+    /// The number of steps before z settled down, or `max_iterations` if it never did.
+    pub iterations: usize,
+    /// Whether z settled down.
+    pub converged: bool,
+    /// The index in [`Newton::roots`] of the root z settled on. `None` for a Nova fractal, or if
+    /// z never settled.
+    pub root: Option<usize>,
+}
+
+impl EscapeValue for Convergence {
+    fn to_f64(self) -> f64 {
+        self.iterations as f64
+    }
+
+    fn root(self) -> Option<usize> {
+        self.root
+    }
+}
+
+impl fmt::Display for Convergence {
+    /// The number of steps, like a plain `usize` grid, followed by `r` and the index of the root
+    /// for points that converged to one.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.root {
+            Some(root) => write!(f, "{}r{}", self.iterations, root),
+            None => write!(f, "{}", self.iterations),
+        }
+    }
+}
+
+/// Runs Newton's method for every pixel of `config.view`, spread over `threads` threads. Only
+/// the view, the iterations and the Julia constant (for a Nova fractal) are taken from `config`.
+///
+/// The grid is indexed as `points[pixel_y][pixel_x]`, as for
+/// [`calculate_mandelbrot`](crate::calculate_mandelbrot).
+pub fn calculate_newton(config: &Config, newton: &Newton, threads: usize) -> Vec<Vec<Convergence>> {
+    let view = &config.view;
+    parallel::map_rows(view.height, threads, |pixel_y| {
+        (0..view.width)
+            .map(|pixel_x| {
+                let point = view.pixel_to_complex(pixel_x, pixel_y);
+                // Newton's method starts at the pixel. A Nova fractal starts at the first root,
+                // which is a fixed point of the plain method, with the pixel as c.
+                let (c, z) = match (newton.nova, config.julia) {
+                    (false, _) => (Complex::new(0.0, 0.0), point),
+                    (true, None) => (point, newton.roots[0]),
+                    (true, Some(c)) => (c, point),
+                };
+                newton.converge(c, z, config.max_iterations)
+            })
+            .collect()
+    })
+}
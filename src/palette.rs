@@ -0,0 +1,137 @@
+//! Colour gradients for the backends that output colour instead of characters.
+
+use crate::mandelbrot::EscapeValue;
+
+/// A named colour gradient. Points inside the set are always drawn black.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    Grayscale,
+    Fire,
+    #[default]
+    Ocean,
+    Rainbow,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Grayscale,
+        Palette::Fire,
+        Palette::Ocean,
+        Palette::Rainbow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Grayscale => "grayscale",
+            Palette::Fire => "fire",
+            Palette::Ocean => "ocean",
+            Palette::Rainbow => "rainbow",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Palette> {
+        Palette::ALL
+            .into_iter()
+            .find(|palette| palette.name() == name)
+    }
+
+    // the colours that the gradient passes through, evenly spaced from 0.0 to 1.0.
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Palette::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+            Palette::Fire => &[
+                [0, 0, 0],
+                [128, 0, 0],
+                [255, 64, 0],
+                [255, 200, 0],
+                [255, 255, 255],
+            ],
+            Palette::Ocean => &[
+                [0, 7, 100],
+                [32, 107, 203],
+                [237, 255, 255],
+                [255, 170, 0],
+                [0, 2, 0],
+            ],
+            Palette::Rainbow => &[
+                [255, 0, 0],
+                [255, 255, 0],
+                [0, 255, 0],
+                [0, 255, 255],
+                [0, 0, 255],
+                [255, 0, 255],
+            ],
+        }
+    }
+
+    /// The colour at position `t` of the gradient. `t` is clamped to `0.0..=1.0`.
+    pub fn color(self, t: f64) -> [u8; 3] {
+        let stops = self.stops();
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+
+        // find the two stops that t lies between, and how far along it is from the first to the second.
+        let scaled = t * (stops.len() - 1) as f64;
+        let index = (scaled as usize).min(stops.len() - 2);
+        let fraction = scaled - index as f64;
+
+        let (from, to) = (stops[index], stops[index + 1]);
+        let mut color = [0; 3];
+        for channel in 0..3 {
+            let value =
+                from[channel] as f64 + (to[channel] as f64 - from[channel] as f64) * fraction;
+            color[channel] = value.round() as u8;
+        }
+        color
+    }
+
+    /// The colour for a pixel from [`calculate_mandelbrot`](crate::calculate_mandelbrot), or from
+    /// [`calculate_mandelbrot_smooth`](crate::calculate_mandelbrot_smooth) for a gradient without bands.
+    ///
+    /// The iteration count is scaled logarithmically, since most of the interesting detail is in
+    /// the low counts, close to the set, while the high counts are few and far between.
+    ///
+    /// Points inside the set are black, unless the period of their orbit is known, see
+    /// [`Palette::color_period`].
+    pub fn color_iterations<V: EscapeValue>(self, value: V, max_iterations: usize) -> [u8; 3] {
+        if let Some(color) = value.color(self) {
+            return color;
+        }
+        let iterations = value.to_f64();
+        if let Some(root) = value.root() {
+            return self.color_root(root, iterations);
+        }
+        if iterations >= max_iterations as f64 {
+            return match value.period() {
+                Some(period) => self.color_period(period),
+                None => [0, 0, 0],
+            };
+        }
+        let t = (1.0 + iterations).ln() / (1.0 + max_iterations as f64).ln();
+        self.color(t)
+    }
+
+    /// A colour for the inside of the set, picked by the period of the orbit.
+    ///
+    /// Neighbouring periods get colours far apart in the gradient, and all of them are darkened,
+    /// so the bulbs stand apart from each other and from the outside of the set.
+    pub fn color_period(self, period: usize) -> [u8; 3] {
+        // stepping around the gradient by the golden ratio never lands on the same spot twice,
+        // and spreads the steps out about as evenly as possible.
+        let t = (period as f64 * 0.618_033_988_749_895).fract();
+        self.color(t).map(|channel| channel / 2)
+    }
+
+    /// A colour for a point of a Newton fractal, picked by the root it converged to, and darker
+    /// the more steps it took to get there.
+    pub fn color_root(self, root: usize, iterations: f64) -> [u8; 3] {
+        // the same golden-ratio steps as for periods, from 1 so the first root is not at the
+        // dark start of most gradients.
+        let t = ((root + 1) as f64 * 0.618_033_988_749_895).fract();
+        // Newton's method usually converges in a handful of steps, far fewer than the maximum,
+        // so the shading falls off with the steps themselves, slowly enough to still tell apart
+        // the tens of steps a relaxed method can take.
+        let shade = 0.3 + 0.7 / (1.0 + iterations / 8.0);
+        self.color(t)
+            .map(|channel| (channel as f64 * shade).round() as u8)
+    }
+}
@@ -0,0 +1,80 @@
+//! Spreads the rows of a grid over several threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The number of threads to use when the caller has no preference: one per CPU core.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Builds a grid of `height` rows by calling `row` for every row index, using up to `threads` threads.
+///
+/// Rows near the edge of the set take far longer than rows far away from it, so the rows are not
+/// split into fixed bands up front. Instead every thread takes the next unclaimed row from a shared
+/// counter as soon as it is done with its previous one, so a thread that got cheap rows simply
+/// takes more of them, and no thread sits idle while there is still work left.
+///
+/// The rows come back in order, and each row is computed exactly as it would be on a single
+/// thread, so the result does not depend on the number of threads.
+pub fn map_rows<T, F>(height: usize, threads: usize, row: F) -> Vec<Vec<T>>
+where
+    T: Send,
+    F: Fn(usize) -> Vec<T> + Sync,
+{
+    let threads = threads.clamp(1, height.max(1));
+    if threads == 1 {
+        return (0..height).map(row).collect();
+    }
+
+    let next_row = AtomicUsize::new(0);
+    let rows: Mutex<Vec<Option<Vec<T>>>> = Mutex::new((0..height).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let pixel_y = next_row.fetch_add(1, Ordering::Relaxed);
+                if pixel_y >= height {
+                    break;
+                }
+                let computed = row(pixel_y);
+                rows.lock().unwrap()[pixel_y] = Some(computed);
+            });
+        }
+    });
+
+    rows.into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|computed| computed.expect("every row is claimed by exactly one thread"))
+        .collect()
+}
+
+/// Calls `task` once for every index below `count`, using up to `threads` threads.
+///
+/// The indices are claimed one at a time from a shared counter, as the rows are in [`map_rows`].
+/// Whatever `task` produces has to be gathered by the task itself, in any order.
+pub fn for_each<F>(count: usize, threads: usize, task: F)
+where
+    F: Fn(usize) + Sync,
+{
+    let threads = threads.clamp(1, count.max(1));
+    if threads == 1 {
+        (0..count).for_each(task);
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= count {
+                    break;
+                }
+                task(index);
+            });
+        }
+    });
+}
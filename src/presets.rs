@@ -0,0 +1,141 @@
+//! A catalogue of famous places in the set, so their coordinates do not have to be looked up.
+//!
+//! Every preset is a [`Location`] with the zoom and iterations it looks good at, which can be
+//! changed like any other location once it is open. Most are places in the Mandelbrot set, and a
+//! few are Julia sets.
+
+use num::complex::Complex;
+
+use crate::formula::Fractal;
+use crate::location::Location;
+use crate::palette::Palette;
+
+/// A named location from the catalogue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    /// One line about what is there, for the `list` command.
+    pub description: &'static str,
+    pub location: Location,
+}
+
+// a preset, with the location spelled out field by field so it can be a constant.
+const fn preset(
+    name: &'static str,
+    description: &'static str,
+    center: (f64, f64),
+    zoom: f64,
+    max_iterations: usize,
+    palette: Palette,
+) -> Preset {
+    Preset {
+        name,
+        description,
+        location: Location {
+            center: Complex {
+                re: center.0,
+                im: center.1,
+            },
+            zoom,
+            max_iterations,
+            palette,
+            julia: None,
+            formula: Fractal::Mandelbrot,
+        },
+    }
+}
+
+// the Julia set of the point c, seen whole.
+const fn julia(
+    name: &'static str,
+    description: &'static str,
+    c: (f64, f64),
+    max_iterations: usize,
+    palette: Palette,
+) -> Preset {
+    let mut preset = preset(name, description, (0.0, 0.0), 1.0, max_iterations, palette);
+    preset.location.julia = Some(Complex { re: c.0, im: c.1 });
+    preset
+}
+
+/// Every preset: first the Julia sets, then the Mandelbrot set from the shallowest zoom to the
+/// deepest.
+pub static PRESETS: [Preset; 9] = [
+    julia(
+        "rabbit",
+        "Douady's rabbit, the Julia set of the center of the period-3 bulb",
+        (-0.122_561_166_876_654, 0.744_861_766_619_744),
+        500,
+        Palette::Ocean,
+    ),
+    julia(
+        "siegel",
+        "A Siegel disk, where the orbits inside circle forever without settling down",
+        (-0.390_540_870_218_4, -0.586_787_907_346_97),
+        1000,
+        Palette::Rainbow,
+    ),
+    preset(
+        "triple-spiral",
+        "Triple Spiral Valley, where the main cardioid meets the period-3 bulb on top",
+        (-0.088, 0.654),
+        40.0,
+        500,
+        Palette::Ocean,
+    ),
+    preset(
+        "elephant",
+        "Elephant Valley, the cleft on the right of the main cardioid, full of trunks",
+        (0.285, 0.01),
+        40.0,
+        500,
+        Palette::Fire,
+    ),
+    preset(
+        "minibrot",
+        "The period-3 minibrot on the needle to the left, a small copy of the whole set",
+        (-1.754_877_666_246_692_7, 0.0),
+        50.0,
+        500,
+        Palette::Rainbow,
+    ),
+    preset(
+        "seahorse",
+        "Seahorse Valley, between the main cardioid and the period-2 bulb",
+        (-0.7453, 0.1127),
+        60.0,
+        500,
+        Palette::Ocean,
+    ),
+    preset(
+        "dendrite",
+        "The tip at c = i, a Misiurewicz point where the set thins out into branches",
+        (0.0, 1.0),
+        200.0,
+        1000,
+        Palette::Grayscale,
+    ),
+    preset(
+        "feigenbaum",
+        "The Feigenbaum point, where the period doublings along the real axis pile up",
+        (-1.401_155_189, 0.0),
+        2000.0,
+        2000,
+        Palette::Fire,
+    ),
+    preset(
+        "spiral",
+        "A Misiurewicz spiral in Seahorse Valley, winding around the point it is centered on",
+        (-0.775_683_77, 0.136_467_37),
+        3000.0,
+        2000,
+        Palette::Rainbow,
+    ),
+];
+
+impl Preset {
+    /// The preset with this name, if there is one.
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+}
@@ -0,0 +1,187 @@
+//! Colour output for the terminal, through ANSI escape sequences.
+//!
+//! Every character cell is drawn as a space on a coloured background. Terminals that cannot show
+//! 24-bit colour get the nearest colour from the xterm 256-colour or the basic 16-colour palette.
+
+use std::env;
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+
+/// How many colours the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit colour, any RGB value.
+    TrueColor,
+    /// The xterm 256-colour palette: a 6x6x6 colour cube plus 24 shades of grey.
+    Xterm256,
+    /// The 8 basic colours and their bright variants.
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorDepth::TrueColor => "truecolor",
+            ColorDepth::Xterm256 => "256",
+            ColorDepth::Ansi16 => "16",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorDepth> {
+        [
+            ColorDepth::TrueColor,
+            ColorDepth::Xterm256,
+            ColorDepth::Ansi16,
+        ]
+        .into_iter()
+        .find(|depth| depth.name() == name)
+    }
+
+    /// Guesses what the terminal supports from the environment, the way most terminal programs do:
+    /// `COLORTERM` is set to `truecolor` or `24bit` by terminals with 24-bit colour,
+    /// and `TERM` ends in `256color` for those with the xterm palette.
+    pub fn detect() -> ColorDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        if term.contains("256color") {
+            return ColorDepth::Xterm256;
+        }
+        ColorDepth::Ansi16
+    }
+
+    /// The escape sequence that sets the background to (the nearest available colour to) `rgb`.
+    pub fn background(self, rgb: [u8; 3]) -> String {
+        match self {
+            ColorDepth::TrueColor => format!("\x1b[48;2;{};{};{}m", rgb[0], rgb[1], rgb[2]),
+            ColorDepth::Xterm256 => format!("\x1b[48;5;{}m", to_xterm256(rgb)),
+            ColorDepth::Ansi16 => format!("\x1b[{}m", ansi16_code(to_ansi16(rgb), 40)),
+        }
+    }
+
+    /// The escape sequence that sets the foreground to (the nearest available colour to) `rgb`.
+    pub fn foreground(self, rgb: [u8; 3]) -> String {
+        match self {
+            ColorDepth::TrueColor => format!("\x1b[38;2;{};{};{}m", rgb[0], rgb[1], rgb[2]),
+            ColorDepth::Xterm256 => format!("\x1b[38;5;{}m", to_xterm256(rgb)),
+            ColorDepth::Ansi16 => format!("\x1b[{}m", ansi16_code(to_ansi16(rgb), 30)),
+        }
+    }
+}
+
+/// Turns all colours back to the terminal's defaults.
+pub const RESET: &str = "\x1b[0m";
+
+/// Writes the grid as coloured cells, one cell per grid point.
+pub fn render_ansi<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    depth: ColorDepth,
+    out: &mut W,
+) -> io::Result<()> {
+    for row in mandelbrot_points {
+        let mut line = String::new();
+
+        // neighbouring cells often share a colour, so only write a new escape sequence
+        // when the colour actually changes.
+        let mut current = None;
+        for &pixel in row {
+            let background = depth.background(palette.color_iterations(pixel, max_iterations));
+            if current.as_ref() != Some(&background) {
+                line.push_str(&background);
+                current = Some(background);
+            }
+            line.push(' ');
+        }
+        // reset before the newline, or the last colour would bleed into the rest of the line.
+        writeln!(out, "{}{}", line, RESET)?;
+    }
+    Ok(())
+}
+
+// the levels the 6 steps of the xterm colour cube use for each channel.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The xterm 256-colour index nearest to `rgb`.
+///
+/// Indexes 16 to 231 form a 6x6x6 colour cube, and 232 to 255 a ramp of greys. Both the
+/// nearest cube colour and the nearest grey are found, and whichever is closer wins.
+pub fn to_xterm256(rgb: [u8; 3]) -> u8 {
+    let cube_step = |channel: u8| -> usize {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &level)| (level as i32 - channel as i32).abs())
+            .map(|(step, _)| step)
+            .unwrap()
+    };
+    let steps = rgb.map(cube_step);
+    let cube_color = steps.map(|step| CUBE_LEVELS[step]);
+    let cube_index = 16 + 36 * steps[0] + 6 * steps[1] + steps[2];
+
+    // the greys go from 8 to 238 in steps of 10.
+    let average = (rgb[0] as u32 + rgb[1] as u32 + rgb[2] as u32) / 3;
+    let grey_step = (average.saturating_sub(3) / 10).min(23);
+    let grey_level = (8 + grey_step * 10) as u8;
+    let grey_index = 232 + grey_step as usize;
+
+    if distance(rgb, [grey_level; 3]) < distance(rgb, cube_color) {
+        grey_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+// the colours xterm uses for the 16 basic colours, in index order.
+const ANSI16_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// The index (0 to 15) of the basic terminal colour nearest to `rgb`.
+pub fn to_ansi16(rgb: [u8; 3]) -> u8 {
+    (0..16)
+        .min_by_key(|&index| distance(rgb, ANSI16_COLORS[index]))
+        .unwrap() as u8
+}
+
+// 30-37 and 90-97 set the foreground, 40-47 and 100-107 the background.
+fn ansi16_code(index: u8, base: u8) -> u8 {
+    if index < 8 {
+        base + index
+    } else {
+        base + 60 + index - 8
+    }
+}
+
+/*
+The squared distance between two colours, with each channel weighted by how sensitive
+the eye is to it (the "redmean" approximation). Plain RGB distance tends to pick greys and
+greens where a person would pick something else.
+*/
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let mean_red = (a[0] as i32 + b[0] as i32) / 2;
+    let [dr, dg, db] = [0, 1, 2].map(|channel| a[channel] as i32 - b[channel] as i32);
+    let red = ((512 + mean_red) * dr * dr) >> 8;
+    let blue = ((767 - mean_red) * db * db) >> 8;
+    (red + 4 * dg * dg + blue) as u32
+}
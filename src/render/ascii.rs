@@ -0,0 +1,85 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::scale::Scale;
+
+/// The characters the ASCII renderer draws with.
+///
+/// The glyphs go from the points that escape right away to those that take the longest.
+/// The last glyph is used for the points inside the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ramp {
+    glyphs: Vec<char>,
+}
+
+impl Ramp {
+    /// Builds a ramp from a string of at least two characters.
+    pub fn new(glyphs: &str) -> Result<Ramp, String> {
+        let glyphs: Vec<char> = glyphs.chars().collect();
+        if glyphs.len() < 2 {
+            return Err("a glyph ramp needs at least two characters".to_string());
+        }
+        Ok(Ramp { glyphs })
+    }
+
+    /// The glyph for a position on the ramp, as given by a [`Scaler`](crate::scale::Scaler).
+    /// 1.0 means inside the set.
+    pub fn glyph(&self, t: f64) -> char {
+        let (inside, outside) = self.glyphs.split_last().unwrap();
+        if t >= 1.0 {
+            return *inside;
+        }
+        let index = (t * outside.len() as f64) as usize;
+        outside[index.min(outside.len() - 1)]
+    }
+}
+
+impl fmt::Display for Ramp {
+    /// Writes the glyphs back out, as they were given to [`Ramp::new`].
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.glyphs
+            .iter()
+            .try_for_each(|glyph| write!(f, "{}", glyph))
+    }
+}
+
+impl Default for Ramp {
+    /// The glyphs this program has always used, with a space for the inside of the set.
+    fn default() -> Ramp {
+        Ramp::new("¸.•›-˛˙˛‘¨¸ ").unwrap()
+    }
+}
+
+/// Replaces each numeric mandelbrot-value in the grid with a char or whitespace.
+/// Then writes each line to `out`, row by row.
+///
+/// The values are spread over the `ramp` by `scale`, relative to `max_iterations`,
+/// so the picture holds up whatever the number of iterations.
+pub fn render_mandelbrot<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    ramp: &Ramp,
+    scale: Scale,
+    out: &mut W,
+) -> io::Result<()> {
+    let scaler = scale.prepare(mandelbrot_points, max_iterations);
+
+    for row in mandelbrot_points {
+        let mut line = String::with_capacity(row.len());
+        //                     ^^^^^^^^^^^^^
+        // Since we know what length the final line-string will have, we can init the String with the with_capacity method.
+        // This does not limit or restrain the String in any way,
+        // it just optimizes the String to not reallocate each time something is appended to the String.
+        // However, a new reallocation would take place if we would add more data beyond its initial buffer length.
+
+        for &pixel in row {
+            // if num of escapes = max_iterations (which means never escaped),
+            // then the pixel was part of the Mandelbrot set, and gets the last glyph.
+            // Every other number of iterations are for displaying the "aura" surrounding the fractals.
+            line.push(ramp.glyph(scaler.map(pixel)));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,502 @@
+/*
+A small streaming zlib (RFC 1950) encoder, so the crate does not need a compression dependency.
+
+The data is compressed with the fixed Huffman codes from the deflate spec (RFC 1951) and LZ77
+matches found through hash chains. Fixed codes are not as tight as the dynamic ones real zlib picks,
+but rendered fractals are mostly long runs of identical colours, which LZ77 alone squeezes well.
+
+Input is buffered until there is a block's worth of it, then compressed as one deflate block.
+Only the last 32 KiB (the furthest back a match may point) are kept around after that,
+so memory use does not depend on how much is written.
+*/
+
+use std::io::{self, Write};
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+// how many earlier positions with the same hash are tried before settling for the best match so far.
+const MAX_CHAIN: usize = 64;
+
+const HASH_BITS: u32 = 15;
+
+// input is compressed in blocks of roughly this size.
+const BLOCK_SIZE: usize = 128 * 1024;
+
+// the base length for each length code 257..=285, followed by the number of extra bits.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// the base distance for each distance code 0..=29, followed by the number of extra bits.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Compresses everything written to it into a zlib stream on `out`.
+/// Call [`ZlibEncoder::finish`] at the end, or the stream is left without its final block.
+pub(crate) struct ZlibEncoder<W: Write> {
+    out: W,
+    bits: BitWriter,
+    adler: Adler32,
+
+    // history (up to WINDOW_SIZE bytes that are already compressed) followed by pending input.
+    buffer: Vec<u8>,
+    // how many bytes were dropped from the front of `buffer` so far.
+    // Positions in `head` and `prev` are absolute, i.e. counted from the start of the stream.
+    buffer_start: usize,
+    // absolute position of the first byte in `buffer` that is not compressed yet.
+    position: usize,
+
+    // most recent absolute position + 1 for each hash, 0 meaning none.
+    head: Vec<usize>,
+    // for each position (modulo the window size), the previous position + 1 with the same hash.
+    prev: Vec<usize>,
+}
+
+impl<W: Write> ZlibEncoder<W> {
+    pub(crate) fn new(mut out: W) -> io::Result<ZlibEncoder<W>> {
+        // CMF: deflate with a 32 KiB window. FLG: no dictionary, default level, plus check bits.
+        out.write_all(&[0x78, 0x9C])?;
+        Ok(ZlibEncoder {
+            out,
+            bits: BitWriter::default(),
+            adler: Adler32::default(),
+            buffer: Vec::with_capacity(WINDOW_SIZE + BLOCK_SIZE + MAX_MATCH),
+            buffer_start: 0,
+            position: 0,
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; WINDOW_SIZE],
+        })
+    }
+
+    /// Writes the final deflate block and the checksum, and hands back the inner writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.compress_block(true)?;
+        self.bits.align();
+        self.bits
+            .bytes
+            .extend_from_slice(&self.adler.value().to_be_bytes());
+        self.out.write_all(&self.bits.bytes)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    // compresses the pending input into one fixed Huffman block. Unless it is the final block,
+    // MAX_MATCH bytes are left over, so matches near the end of the block are not cut short.
+    fn compress_block(&mut self, is_final: bool) -> io::Result<()> {
+        let end = self.buffer_start + self.buffer.len();
+        let limit = if is_final { end } else { end - MAX_MATCH };
+
+        // block header: BFINAL, then BTYPE 01 (fixed Huffman codes), least significant bit first.
+        self.bits.write(is_final as u32, 1);
+        self.bits.write(1, 2);
+
+        while self.position < limit {
+            let (length, distance) = self.longest_match(self.position, end);
+            if length >= MIN_MATCH {
+                self.bits.write_length(length);
+                self.bits.write_distance(distance);
+                for offset in 0..length {
+                    self.insert(self.position + offset, end);
+                }
+                self.position += length;
+            } else {
+                let literal = self.buffer[self.position - self.buffer_start];
+                self.bits.write_literal(literal as u16);
+                self.insert(self.position, end);
+                self.position += 1;
+            }
+        }
+        self.bits.write_literal(256);
+
+        self.out.write_all(&self.bits.bytes)?;
+        self.bits.bytes.clear();
+
+        // drop everything that is too far back to be matched against any more.
+        let keep_from = self.position.saturating_sub(WINDOW_SIZE);
+        if keep_from > self.buffer_start {
+            self.buffer.drain(..keep_from - self.buffer_start);
+            self.buffer_start = keep_from;
+        }
+        Ok(())
+    }
+
+    fn hash(&self, position: usize) -> usize {
+        let index = position - self.buffer_start;
+        let bytes = &self.buffer[index..index + MIN_MATCH];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, position: usize, end: usize) {
+        if position + MIN_MATCH > end {
+            return;
+        }
+        let hash = self.hash(position);
+        self.prev[position % WINDOW_SIZE] = self.head[hash];
+        self.head[hash] = position + 1;
+    }
+
+    // walks the hash chain for `position` and returns the longest (length, distance) found.
+    fn longest_match(&self, position: usize, end: usize) -> (usize, usize) {
+        let max_length = MAX_MATCH.min(end - position);
+        if max_length < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let current = &self.buffer[position - self.buffer_start..];
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash(position)];
+        for _ in 0..MAX_CHAIN {
+            // 0 ends the chain. Entries can also be stale: left over from a position that
+            // has been dropped from the buffer, or overwritten by a newer one.
+            if candidate == 0 {
+                break;
+            }
+            let start = candidate - 1;
+            if start >= position || start < self.buffer_start {
+                break;
+            }
+            let distance = position - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+
+            let earlier = &self.buffer[start - self.buffer_start..];
+            let length = current
+                .iter()
+                .zip(earlier)
+                .take(max_length)
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, distance);
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.prev[start % WINDOW_SIZE];
+        }
+        best
+    }
+}
+
+impl<W: Write> Write for ZlibEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.adler.update(data);
+        self.buffer.extend_from_slice(data);
+
+        let end = self.buffer_start + self.buffer.len();
+        if end - self.position >= BLOCK_SIZE + MAX_MATCH {
+            self.compress_block(false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// collects bits least significant bit first, as deflate wants them.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are defined most significant bit first, so they go in reversed.
+    fn write_code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn write_literal(&mut self, literal: u16) {
+        let literal = literal as u32;
+        match literal {
+            0..=143 => self.write_code(0x30 + literal, 8),
+            144..=255 => self.write_code(0x190 + literal - 144, 9),
+            256..=279 => self.write_code(literal - 256, 7),
+            _ => self.write_code(0xC0 + literal - 280, 8),
+        }
+    }
+
+    fn write_length(&mut self, length: usize) {
+        let code = LENGTH_BASE
+            .iter()
+            .rposition(|&base| base as usize <= length)
+            .unwrap();
+        self.write_literal(257 + code as u16);
+        self.write(
+            (length - LENGTH_BASE[code] as usize) as u32,
+            LENGTH_EXTRA[code] as u32,
+        );
+    }
+
+    fn write_distance(&mut self, distance: usize) {
+        let code = DISTANCE_BASE
+            .iter()
+            .rposition(|&base| base as usize <= distance)
+            .unwrap();
+        self.write_code(code as u32, 5);
+        self.write(
+            (distance - DISTANCE_BASE[code] as usize) as u32,
+            DISTANCE_EXTRA[code] as u32,
+        );
+    }
+
+    // pads with zero bits up to the next byte boundary.
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+}
+
+// the checksum at the end of a zlib stream.
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Default for Adler32 {
+    fn default() -> Adler32 {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        // 5552 is the most bytes that can be summed before `b` could overflow a u32.
+        for chunk in data.chunks(5552) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= 65521;
+            self.b %= 65521;
+        }
+    }
+
+    fn value(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // what came out of a zlib stream, and how: the back-references as (length, distance), and
+    // the number of deflate blocks.
+    pub(crate) struct Inflated {
+        pub(crate) data: Vec<u8>,
+        pub(crate) matches: Vec<(usize, usize)>,
+        pub(crate) blocks: usize,
+    }
+
+    // reads bits least significant bit first, the way BitWriter writes them.
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let bit = (self.bytes[self.position / 8] >> (self.position % 8)) & 1;
+            self.position += 1;
+            bit as u32
+        }
+
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, index| value | self.bit() << index)
+        }
+
+        // Huffman codes come most significant bit first.
+        fn code(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |code, _| code << 1 | self.bit())
+        }
+
+        fn literal(&mut self) -> u16 {
+            let mut code = self.code(7);
+            if code <= 23 {
+                return 256 + code as u16;
+            }
+            code = code << 1 | self.bit();
+            match code {
+                0x30..=0xBF => return (code - 0x30) as u16,
+                0xC0..=0xC7 => return (280 + code - 0xC0) as u16,
+                _ => {}
+            }
+            code = code << 1 | self.bit();
+            (144 + code - 0x190) as u16
+        }
+    }
+
+    // a decoder for the subset of zlib the encoder writes: fixed Huffman blocks only. It checks
+    // the header and the Adler-32 checksum on the way.
+    pub(crate) fn inflate(zlib: &[u8]) -> Inflated {
+        assert_eq!(&zlib[..2], &[0x78, 0x9C]);
+        assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+        let mut reader = BitReader {
+            bytes: &zlib[..zlib.len() - 4],
+            position: 16,
+        };
+        let mut inflated = Inflated {
+            data: Vec::new(),
+            matches: Vec::new(),
+            blocks: 0,
+        };
+        loop {
+            let is_final = reader.bit() == 1;
+            assert_eq!(reader.bits(2), 1, "only fixed Huffman blocks are written");
+            inflated.blocks += 1;
+            loop {
+                let symbol = reader.literal();
+                match symbol {
+                    0..=255 => inflated.data.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let code = (symbol - 257) as usize;
+                        let length = LENGTH_BASE[code] as usize
+                            + reader.bits(LENGTH_EXTRA[code] as u32) as usize;
+                        let code = reader.code(5) as usize;
+                        let distance = DISTANCE_BASE[code] as usize
+                            + reader.bits(DISTANCE_EXTRA[code] as u32) as usize;
+                        assert!(distance <= inflated.data.len(), "distance before the start");
+                        for _ in 0..length {
+                            let byte = inflated.data[inflated.data.len() - distance];
+                            inflated.data.push(byte);
+                        }
+                        inflated.matches.push((length, distance));
+                    }
+                }
+            }
+            if is_final {
+                break;
+            }
+        }
+        // everything after the final block is padding, up to the checksum.
+        assert_eq!(reader.position.div_ceil(8), zlib.len() - 4);
+        let mut adler = Adler32::default();
+        adler.update(&inflated.data);
+        assert_eq!(zlib[zlib.len() - 4..], adler.value().to_be_bytes());
+        inflated
+    }
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new()).unwrap();
+        // in uneven pieces, as rows of an image would come.
+        for piece in data.chunks(1000) {
+            encoder.write_all(piece).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    // bytes that LZ77 finds nothing in: xorshift noise.
+    fn noise(length: usize, mut state: u32) -> Vec<u8> {
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adler32_known_values() {
+        let mut adler = Adler32::default();
+        assert_eq!(adler.value(), 1);
+        adler.update(b"123456789");
+        assert_eq!(adler.value(), 0x091E_01DE);
+
+        let mut adler = Adler32::default();
+        adler.update(b"Wikipedia");
+        assert_eq!(adler.value(), 0x11E6_0398);
+
+        // long enough for the sums to be reduced along the way.
+        let mut adler = Adler32::default();
+        adler.update(&[0xFF; 100_000]);
+        let (mut a, mut b) = (1u64, 0u64);
+        for _ in 0..100_000 {
+            a = (a + 0xFF) % 65521;
+            b = (b + a) % 65521;
+        }
+        assert_eq!(adler.value(), (b << 16 | a) as u32);
+    }
+
+    #[test]
+    fn empty_and_short_input() {
+        for data in [&b""[..], b"a", b"ab", b"abc", b"abcabcabc"] {
+            assert_eq!(inflate(&compress(data)).data, data);
+        }
+    }
+
+    #[test]
+    fn runs_become_long_matches() {
+        let data = vec![7; 10_000];
+        let compressed = compress(&data);
+        let inflated = inflate(&compressed);
+        assert_eq!(inflated.data, data);
+        // one literal, then matches of the longest length one byte back.
+        assert!(inflated.matches.contains(&(MAX_MATCH, 1)));
+        assert!(compressed.len() < 100);
+    }
+
+    #[test]
+    fn matches_reach_the_whole_window() {
+        // the same noise twice, a window apart: the only way to shrink it is to reach all the
+        // way back.
+        let block = noise(WINDOW_SIZE, 1);
+        let data = [&block[..], &block[..]].concat();
+        let inflated = inflate(&compress(&data));
+        assert_eq!(inflated.data, data);
+        assert!(inflated
+            .matches
+            .iter()
+            .any(|&(_, distance)| distance == WINDOW_SIZE));
+        assert!(inflated
+            .matches
+            .iter()
+            .all(|&(_, distance)| distance <= WINDOW_SIZE));
+    }
+
+    #[test]
+    fn input_across_blocks() {
+        // noise with repeats in it, over more than two blocks, so matches cross block edges.
+        let mut data = Vec::new();
+        for part in 0..40 {
+            let piece = noise(5000, part % 7 + 1);
+            data.extend_from_slice(&piece);
+            data.extend_from_slice(&[part as u8; 3000]);
+        }
+        assert!(data.len() > 2 * BLOCK_SIZE);
+        let inflated = inflate(&compress(&data));
+        assert_eq!(inflated.data, data);
+        assert!(inflated.blocks >= 3);
+    }
+}
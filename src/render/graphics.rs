@@ -0,0 +1,61 @@
+//! Pixel images drawn straight into the terminal, for terminals with a graphics protocol.
+
+use std::env;
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+use crate::render::{kitty, sixel};
+
+/// The escape sequences used to send the image to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// DEC Sixel, with the colours reduced to a palette of at most 256.
+    Sixel,
+    /// The Kitty graphics protocol, with full 24-bit colour.
+    Kitty,
+}
+
+impl Protocol {
+    pub fn name(self) -> &'static str {
+        match self {
+            Protocol::Sixel => "sixel",
+            Protocol::Kitty => "kitty",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Protocol> {
+        [Protocol::Sixel, Protocol::Kitty]
+            .into_iter()
+            .find(|protocol| protocol.name() == name)
+    }
+
+    /// Guesses the protocol from the environment: kitty sets `KITTY_WINDOW_ID` and a `TERM` of
+    /// `xterm-kitty`, and WezTerm sets `TERM_PROGRAM`. Everything else gets sixel, which is the
+    /// more widely supported of the two.
+    pub fn detect() -> Protocol {
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term_program == "WezTerm"
+        {
+            return Protocol::Kitty;
+        }
+        Protocol::Sixel
+    }
+}
+
+/// Writes the grid as an image in the given protocol, one pixel per grid point.
+pub fn write_image<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    protocol: Protocol,
+    out: &mut W,
+) -> io::Result<()> {
+    match protocol {
+        Protocol::Sixel => sixel::write_sixel(mandelbrot_points, max_iterations, palette, out),
+        Protocol::Kitty => kitty::write_kitty(mandelbrot_points, max_iterations, palette, out),
+    }
+}
@@ -0,0 +1,72 @@
+//! Inline images through the Kitty graphics protocol, understood by kitty, WezTerm, Konsole
+//! and others.
+//!
+//! The raw RGBA pixels are base64 encoded and sent in chunks of at most 4096 bytes, each chunk
+//! wrapped in its own `ESC _ G ... ESC \` escape sequence. Every chunk but the last has `m=1`,
+//! telling the terminal that more data follows.
+
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+
+// the protocol's limit for the base64 data in a single escape sequence.
+const CHUNK_SIZE: usize = 4096;
+
+/// Writes the grid as a Kitty graphics image, one pixel per grid point, followed by a newline.
+pub fn write_kitty<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    out: &mut W,
+) -> io::Result<()> {
+    let width = mandelbrot_points.first().map_or(0, |row| row.len());
+    let height = mandelbrot_points.len();
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for &pixel in mandelbrot_points.iter().flatten() {
+        rgba.extend_from_slice(&palette.color_iterations(pixel, max_iterations));
+        rgba.push(255);
+    }
+    let encoded = base64(&rgba);
+
+    // a=T transmits and displays the image at once. f=32 means 8-bit RGBA, s and v give the size.
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(CHUNK_SIZE).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = (index + 1 < chunks.len()) as u8;
+        if index == 0 {
+            write!(out, "\x1b_Ga=T,f=32,s={},v={},m={};", width, height, more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    writeln!(out)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// standard base64 (RFC 4648), with '=' padding.
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        // pack up to 3 bytes into 24 bits, and cut those into four 6-bit letters.
+        let bytes = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for letter in 0..4 {
+            if letter <= group.len() {
+                let index = (bits >> (18 - letter * 6)) & 0x3F;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,15 @@
+//! Backends that turn the grid from [`calculate_mandelbrot`](crate::calculate_mandelbrot) into output.
+//!
+//! Every backend writes to any [`std::io::Write`], so the same code can print to the terminal,
+//! write a file or fill a buffer in memory.
+
+pub mod ansi;
+pub mod ascii;
+mod deflate;
+pub mod graphics;
+pub mod kitty;
+pub mod netpbm;
+pub mod png;
+pub mod raw;
+pub mod sixel;
+pub mod subcell;
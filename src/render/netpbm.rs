@@ -0,0 +1,87 @@
+//! Netpbm output: PPM for colour, 16-bit PGM for the raw iteration counts and PFM for floats.
+//!
+//! These formats are just a short text header followed by the pixels, so tools like ImageMagick
+//! and numpy can read them without any palette getting in between the data and the reader.
+
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+
+/// Writes the grid as a binary PPM (P6) with 8 bits per channel, coloured with `palette`.
+pub fn write_ppm<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    mut out: W,
+) -> io::Result<()> {
+    let (width, height) = dimensions(mandelbrot_points);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+
+    let mut rgb = Vec::with_capacity(width * 3);
+    for row in mandelbrot_points {
+        rgb.clear();
+        for &pixel in row {
+            rgb.extend_from_slice(&palette.color_iterations(pixel, max_iterations));
+        }
+        out.write_all(&rgb)?;
+    }
+    out.flush()
+}
+
+/// Writes the raw iteration counts as a binary PGM (P5).
+///
+/// The maximum grey value is `max_iterations`, so points inside the set are white and no
+/// information is lost to an 8-bit clamp. The format tops out at 16 bits, so counts above
+/// 65535 are stored as 65535. A max value above 255 means two bytes per pixel, big endian.
+/// Smooth counts are rounded to the nearest whole number.
+pub fn write_pgm<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    mut out: W,
+) -> io::Result<()> {
+    let (width, height) = dimensions(mandelbrot_points);
+    let max_value = max_iterations.clamp(1, u16::MAX as usize);
+    write!(out, "P5\n{} {}\n{}\n", width, height, max_value)?;
+
+    let mut bytes = Vec::with_capacity(width * 2);
+    for row in mandelbrot_points {
+        bytes.clear();
+        for &pixel in row {
+            let value = pixel.to_f64().round().min(max_value as f64) as u16;
+            if max_value > 255 {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            } else {
+                bytes.push(value as u8);
+            }
+        }
+        out.write_all(&bytes)?;
+    }
+    out.flush()
+}
+
+/// Writes a grid as a greyscale PFM (Pf), one 32-bit float per pixel. This is the format that keeps
+/// the smooth counts from [`calculate_mandelbrot_smooth`](crate::calculate_mandelbrot_smooth) intact.
+///
+/// PFM stores its rows from the bottom of the image up, so the rows are written in reverse to make
+/// the picture come out the same way up as the other formats.
+pub fn write_pfm<V: EscapeValue, W: Write>(values: &[Vec<V>], mut out: W) -> io::Result<()> {
+    let width = values.first().map_or(0, |row| row.len());
+    // a negative scale means the floats are little endian.
+    write!(out, "Pf\n{} {}\n-1.0\n", width, values.len())?;
+
+    let mut bytes = Vec::with_capacity(width * 4);
+    for row in values.iter().rev() {
+        bytes.clear();
+        for &value in row {
+            bytes.extend_from_slice(&(value.to_f64() as f32).to_le_bytes());
+        }
+        out.write_all(&bytes)?;
+    }
+    out.flush()
+}
+
+fn dimensions<V>(mandelbrot_points: &[Vec<V>]) -> (usize, usize) {
+    let width = mandelbrot_points.first().map_or(0, |row| row.len());
+    (width, mandelbrot_points.len())
+}
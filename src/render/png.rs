@@ -0,0 +1,513 @@
+//! PNG output, with its own encoder so the crate does not need an image dependency.
+//!
+//! Rows are coloured, compressed and written one at a time, so even an 8K image never needs
+//! a second full-size buffer next to the iteration grid.
+
+use std::io::{self, Read, Write};
+
+use super::deflate::ZlibEncoder;
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+// compressed data is split into IDAT chunks of this size.
+const IDAT_SIZE: usize = 64 * 1024;
+
+/// Writes the grid from [`calculate_mandelbrot`](crate::calculate_mandelbrot) (or its smooth
+/// variant) as a true-colour PNG, one pixel per grid point.
+pub fn write_png<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    out: W,
+) -> io::Result<()> {
+    write_png_with_text(mandelbrot_points, max_iterations, palette, &[], out)
+}
+
+/// Like [`write_png`], with text chunks of `(keyword, text)` in front of the image data, such as
+/// the [`Scene`](crate::scene::Scene) it was rendered from. [`read_text`] reads them back.
+pub fn write_png_with_text<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    text: &[(&str, &str)],
+    out: W,
+) -> io::Result<()> {
+    let width = mandelbrot_points.first().map_or(0, |row| row.len());
+    let mut encoder = PngEncoder::with_text(out, width, mandelbrot_points.len(), text)?;
+
+    let mut rgb = Vec::with_capacity(width * 3);
+    for row in mandelbrot_points {
+        rgb.clear();
+        for &pixel in row {
+            rgb.extend_from_slice(&palette.color_iterations(pixel, max_iterations));
+        }
+        encoder.write_row(&rgb)?;
+    }
+    encoder.finish()?;
+    Ok(())
+}
+
+/// A streaming PNG encoder for 8-bit RGB images.
+///
+/// The header is written by [`PngEncoder::new`], then every row is handed to
+/// [`PngEncoder::write_row`] from top to bottom, and [`PngEncoder::finish`] closes the file.
+pub struct PngEncoder<W: Write> {
+    zlib: ZlibEncoder<IdatWriter<W>>,
+    width: usize,
+    height: usize,
+    rows_written: usize,
+}
+
+impl<W: Write> PngEncoder<W> {
+    pub fn new(out: W, width: usize, height: usize) -> io::Result<PngEncoder<W>> {
+        PngEncoder::with_text(out, width, height, &[])
+    }
+
+    /// Like [`PngEncoder::new`], with a text chunk for every `(keyword, text)` pair after the
+    /// header. Keywords are 1 to 79 printable ASCII characters, without leading, trailing or
+    /// double spaces. PNG also allows the rest of Latin-1 in keywords, but those characters are
+    /// two bytes in a `&str`, so they are turned down rather than converted.
+    pub fn with_text(
+        mut out: W,
+        width: usize,
+        height: usize,
+        text: &[(&str, &str)],
+    ) -> io::Result<PngEncoder<W>> {
+        if width == 0 || height == 0 || width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("a PNG cannot be {}x{} pixels", width, height),
+            ));
+        }
+
+        out.write_all(&SIGNATURE)?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        // bit depth 8, colour type 2 (RGB), deflate compression, adaptive filtering, no interlace.
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(&mut out, b"IHDR", &header)?;
+        for (keyword, text) in text {
+            write_text_chunk(&mut out, keyword, text)?;
+        }
+
+        let idat = IdatWriter {
+            out,
+            buffer: Vec::with_capacity(IDAT_SIZE),
+        };
+        Ok(PngEncoder {
+            zlib: ZlibEncoder::new(idat)?,
+            width,
+            height,
+            rows_written: 0,
+        })
+    }
+
+    /// Writes the next row, given as `width * 3` bytes of red, green and blue.
+    pub fn write_row(&mut self, rgb: &[u8]) -> io::Result<()> {
+        if rgb.len() != self.width * 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected {} bytes for a row, got {}",
+                    self.width * 3,
+                    rgb.len()
+                ),
+            ));
+        }
+        if self.rows_written == self.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "all rows have already been written",
+            ));
+        }
+
+        // every row starts with its filter type. 0 means no filter: LZ77 already finds the
+        // runs of equal colours, which are what a fractal image mostly consists of.
+        self.zlib.write_all(&[0])?;
+        self.zlib.write_all(rgb)?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Writes the remaining image data and the end of the file, and hands back the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        if self.rows_written != self.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "only {} of {} rows were written",
+                    self.rows_written, self.height
+                ),
+            ));
+        }
+        let idat = self.zlib.finish()?;
+        let mut out = idat.finish()?;
+        write_chunk(&mut out, b"IEND", &[])?;
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+// collects the zlib stream and writes it out as a series of IDAT chunks.
+struct IdatWriter<W: Write> {
+    out: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> IdatWriter<W> {
+    fn finish(mut self) -> io::Result<W> {
+        if !self.buffer.is_empty() {
+            write_chunk(&mut self.out, b"IDAT", &self.buffer)?;
+        }
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for IdatWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() >= IDAT_SIZE {
+            write_chunk(&mut self.out, b"IDAT", &self.buffer[..IDAT_SIZE])?;
+            self.buffer.drain(..IDAT_SIZE);
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/*
+Text goes in a tEXt chunk when it is plain ASCII, which every PNG viewer can show:
+  keyword, 0, text
+and in an iTXt chunk otherwise, since tEXt is limited to Latin-1 and the text is UTF-8:
+  keyword, 0, compression flag (0: none), compression method (0), language, 0,
+  translated keyword, 0, text
+The language and translated keyword are left empty.
+*/
+fn write_text_chunk<W: Write>(out: &mut W, keyword: &str, text: &str) -> io::Result<()> {
+    let valid_keyword = (1..=79).contains(&keyword.len())
+        && keyword.bytes().all(|byte| (b' '..=b'~').contains(&byte))
+        && !keyword.starts_with(' ')
+        && !keyword.ends_with(' ')
+        && !keyword.contains("  ");
+    if !valid_keyword {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid PNG text keyword", keyword),
+        ));
+    }
+
+    let mut data = Vec::with_capacity(keyword.len() + text.len() + 5);
+    data.extend_from_slice(keyword.as_bytes());
+    data.push(0);
+    if text.is_ascii() {
+        data.extend_from_slice(text.as_bytes());
+        write_chunk(out, b"tEXt", &data)
+    } else {
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(text.as_bytes());
+        write_chunk(out, b"iTXt", &data)
+    }
+}
+
+/// Reads the text chunks of a PNG file as `(keyword, text)` pairs, in the order they appear.
+///
+/// Both tEXt and iTXt chunks are read, wherever they are in the file. Compressed text (zTXt, or
+/// iTXt with its compression flag set) is skipped, since there is no decompressor here to read
+/// it with, and this crate never writes it.
+pub fn read_text<R: Read>(mut input: R) -> io::Result<Vec<(String, String)>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let mut signature = [0; 8];
+    input.read_exact(&mut signature)?;
+    if signature != SIGNATURE {
+        return Err(invalid("not a PNG file"));
+    }
+
+    let mut text = Vec::new();
+    loop {
+        let mut header = [0; 8];
+        input.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let kind = [header[4], header[5], header[6], header[7]];
+
+        // only text chunks are kept in memory, the image data is skipped over.
+        if &kind != b"tEXt" && &kind != b"iTXt" {
+            let skipped = io::copy(&mut (&mut input).take(length + 4), &mut io::sink())?;
+            if skipped != length + 4 {
+                return Err(invalid("the PNG file ends in the middle of a chunk"));
+            }
+            if &kind == b"IEND" {
+                return Ok(text);
+            }
+            continue;
+        }
+
+        // read through take instead of into a buffer of the given length, so that a damaged
+        // length cannot make it allocate gigabytes up front: the Vec grows as the data arrives.
+        let mut data = Vec::new();
+        (&mut input).take(length).read_to_end(&mut data)?;
+        if data.len() as u64 != length {
+            return Err(invalid("the PNG file ends in the middle of a chunk"));
+        }
+        let mut crc = [0; 4];
+        input.read_exact(&mut crc)?;
+        if u32::from_be_bytes(crc) != !crc32_update(crc32_update(!0, &kind), &data) {
+            return Err(invalid("a text chunk of the PNG file is damaged"));
+        }
+
+        let (keyword, rest) = split_at_zero(&data).ok_or_else(|| invalid("invalid text chunk"))?;
+        // tEXt is Latin-1, where every byte is the Unicode character of the same number.
+        let keyword: String = keyword.iter().map(|&byte| byte as char).collect();
+        if &kind == b"tEXt" {
+            text.push((keyword, rest.iter().map(|&byte| byte as char).collect()));
+            continue;
+        }
+
+        let (&compressed, rest) = rest
+            .split_first()
+            .ok_or_else(|| invalid("invalid iTXt chunk"))?;
+        let (_language, rest) = rest
+            .get(1..)
+            .and_then(split_at_zero)
+            .ok_or_else(|| invalid("invalid iTXt chunk"))?;
+        let (_translated, rest) =
+            split_at_zero(rest).ok_or_else(|| invalid("invalid iTXt chunk"))?;
+        if compressed == 0 {
+            let value = String::from_utf8(rest.to_vec())
+                .map_err(|_| invalid("an iTXt chunk is not valid UTF-8"))?;
+            text.push((keyword, value));
+        }
+    }
+}
+
+// splits at the first zero byte, leaving it out of both halves.
+fn split_at_zero(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let zero = data.iter().position(|&byte| byte == 0)?;
+    Some((&data[..zero], &data[zero + 1..]))
+}
+
+/*
+Every chunk is laid out as:
+  length (4 bytes, big endian, counts only the data)
+  type   (4 ascii letters)
+  data
+  crc    (4 bytes, over the type and the data)
+*/
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = !crc32_update(crc32_update(!0, kind), data);
+    out.write_all(&crc.to_be_bytes())
+}
+
+// the CRC-32 lookup table for the polynomial used by PNG (and zip, and ethernet).
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::deflate::tests::inflate;
+    use super::*;
+
+    struct Chunk {
+        kind: [u8; 4],
+        data: Vec<u8>,
+    }
+
+    // splits a PNG file into its chunks, checking the signature, every length and every CRC.
+    fn chunks(png: &[u8]) -> Vec<Chunk> {
+        assert_eq!(png[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+            let data = &rest[8..8 + length];
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(crc, !crc32_update(crc32_update(!0, &kind), data));
+            chunks.push(Chunk {
+                kind,
+                data: data.to_vec(),
+            });
+            rest = &rest[12 + length..];
+        }
+        chunks
+    }
+
+    fn encode(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 3]) -> Vec<u8> {
+        let mut encoder =
+            PngEncoder::with_text(Vec::new(), width, height, &[("Title", "Test")]).unwrap();
+        for y in 0..height {
+            let row: Vec<u8> = (0..width).flat_map(|x| pixel(x, y)).collect();
+            encoder.write_row(&row).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    // the image data as it goes into the compressor: every row behind a filter type byte of 0.
+    fn filtered(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 3]) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| {
+                let mut row = vec![0];
+                row.extend((0..width).flat_map(|x| pixel(x, y)));
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crc32_known_values() {
+        assert_eq!(!crc32_update(!0, b""), 0);
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF4_3926);
+        // the CRC of an empty IEND chunk, which is the same in every PNG file.
+        assert_eq!(!crc32_update(!0, b"IEND"), 0xAE42_6082);
+        // updating in pieces is the same as all at once.
+        assert_eq!(
+            crc32_update(crc32_update(!0, b"1234"), b"56789"),
+            crc32_update(!0, b"123456789")
+        );
+    }
+
+    #[test]
+    fn small_image_layout() {
+        let pixel = |x: usize, y: usize| [x as u8 * 40, y as u8 * 60, 200];
+        let png = encode(5, 3, pixel);
+        let chunks = chunks(&png);
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|chunk| &chunk.kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"tEXt", b"IDAT", b"IEND"]);
+
+        assert_eq!(chunks[0].data, [0, 0, 0, 5, 0, 0, 0, 3, 8, 2, 0, 0, 0]);
+        assert_eq!(chunks[1].data, b"Title\0Test");
+        assert_eq!(inflate(&chunks[2].data).data, filtered(5, 3, pixel));
+        assert!(chunks[3].data.is_empty());
+    }
+
+    #[test]
+    fn large_image_is_split_into_idat_chunks() {
+        // noise compresses poorly, so the zlib stream is bigger than several IDAT chunks.
+        let pixel = |x: usize, y: usize| {
+            let hash = ((x * 7919 + y * 104_729) as u32).wrapping_mul(0x9E37_79B9);
+            [(hash >> 24) as u8, (hash >> 16) as u8, (hash >> 8) as u8]
+        };
+        let (width, height) = (300, 250);
+        let png = encode(width, height, pixel);
+        let chunks = chunks(&png);
+
+        let idat: Vec<&Chunk> = chunks
+            .iter()
+            .filter(|chunk| &chunk.kind == b"IDAT")
+            .collect();
+        assert!(idat.len() >= 3);
+        // the IDAT chunks are consecutive, and all but the last are full.
+        let first = chunks
+            .iter()
+            .position(|chunk| &chunk.kind == b"IDAT")
+            .unwrap();
+        assert!(chunks[first..first + idat.len()]
+            .iter()
+            .all(|chunk| &chunk.kind == b"IDAT"));
+        assert!(idat[..idat.len() - 1]
+            .iter()
+            .all(|chunk| chunk.data.len() == IDAT_SIZE));
+        assert!(idat[idat.len() - 1].data.len() <= IDAT_SIZE);
+        assert_eq!(&chunks.last().unwrap().kind, b"IEND");
+
+        let zlib: Vec<u8> = idat.iter().flat_map(|chunk| chunk.data.clone()).collect();
+        assert_eq!(inflate(&zlib).data, filtered(width, height, pixel));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = [("Title", "plain"), ("Comment", "z² + c")];
+        let mut encoder = PngEncoder::with_text(Vec::new(), 1, 1, &text).unwrap();
+        encoder.write_row(&[1, 2, 3]).unwrap();
+        let png = encoder.finish().unwrap();
+        let kinds: Vec<[u8; 4]> = chunks(&png).iter().map(|chunk| chunk.kind).collect();
+        assert_eq!(kinds[1..3], [*b"tEXt", *b"iTXt"]);
+
+        let read = read_text(&png[..]).unwrap();
+        let expected: Vec<(String, String)> = text
+            .iter()
+            .map(|&(keyword, text)| (keyword.to_string(), text.to_string()))
+            .collect();
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn truncated_text_chunk() {
+        let mut encoder = PngEncoder::with_text(Vec::new(), 1, 1, &[("Title", "plain")]).unwrap();
+        encoder.write_row(&[1, 2, 3]).unwrap();
+        let mut png = encoder.finish().unwrap();
+        // the tEXt chunk comes right after the 8 byte signature and the 25 byte IHDR chunk.
+        let text = 8 + 25;
+        assert_eq!(&png[text + 4..text + 8], b"tEXt");
+        png[text..text + 4].copy_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+        png.truncate(text + 20);
+
+        let error = read_text(&png[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "the PNG file ends in the middle of a chunk"
+        );
+    }
+
+    #[test]
+    fn keywords_are_checked() {
+        let long = "k".repeat(79);
+        for keyword in ["Title", "a b", "~!", long.as_str()] {
+            assert!(PngEncoder::with_text(Vec::new(), 1, 1, &[(keyword, "")]).is_ok());
+        }
+        let too_long = "k".repeat(80);
+        for keyword in ["", " a", "a ", "a  b", "é", "a\tb", too_long.as_str()] {
+            assert!(
+                PngEncoder::with_text(Vec::new(), 1, 1, &[(keyword, "")]).is_err(),
+                "{:?}",
+                keyword
+            );
+        }
+    }
+
+    #[test]
+    fn rows_are_checked() {
+        let mut encoder = PngEncoder::new(Vec::new(), 2, 1).unwrap();
+        assert!(encoder.write_row(&[0; 5]).is_err());
+        encoder.write_row(&[0; 6]).unwrap();
+        assert!(encoder.write_row(&[0; 6]).is_err());
+        assert!(PngEncoder::new(Vec::new(), 2, 2).unwrap().finish().is_err());
+        assert!(PngEncoder::new(Vec::new(), 0, 2).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+
+/// Writes the raw number of iterations for each pixel, separated by spaces, row by row.
+/// Handy when the output is piped into other tools instead of being looked at.
+pub fn render_raw<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    out: &mut W,
+) -> io::Result<()> {
+    for row in mandelbrot_points {
+        let line: Vec<String> = row.iter().map(|pixel| pixel.to_string()).collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! Inline images in the DEC Sixel format, understood by xterm (with `-ti vt340`), mlterm, foot,
+//! WezTerm and others.
+//!
+//! A sixel is a column of 6 pixels, written as a single character. The image is drawn in bands
+//! 6 pixels high, and each band is drawn once per colour, with a sixel for every column that has
+//! that colour somewhere in its 6 pixels. Sixel images have at most 256 colours, so the colours of
+//! the image are first reduced to a palette with the median cut algorithm.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+
+/// The most colours a sixel image can have.
+pub const MAX_COLORS: usize = 256;
+
+/// Writes the grid as a sixel image, one pixel per grid point.
+pub fn write_sixel<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    out: &mut W,
+) -> io::Result<()> {
+    let width = mandelbrot_points.first().map_or(0, |row| row.len());
+    let height = mandelbrot_points.len();
+
+    // colour every pixel, and count how often each colour is used.
+    let pixels: Vec<Vec<[u8; 3]>> = mandelbrot_points
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&pixel| palette.color_iterations(pixel, max_iterations))
+                .collect()
+        })
+        .collect();
+    let mut histogram = BTreeMap::new();
+    for &color in pixels.iter().flatten() {
+        *histogram.entry(color).or_insert(0) += 1;
+    }
+    let histogram: Vec<([u8; 3], usize)> = histogram.into_iter().collect();
+    let colors = median_cut(&histogram, MAX_COLORS);
+
+    // every distinct colour of the image is looked up in the palette only once.
+    let mut nearest: HashMap<[u8; 3], usize> = HashMap::new();
+    for &(color, _) in &histogram {
+        nearest.insert(color, nearest_color(&colors, color));
+    }
+    let indexes: Vec<Vec<usize>> = pixels
+        .iter()
+        .map(|row| row.iter().map(|color| nearest[color]).collect())
+        .collect();
+
+    // DCS q starts the sixel data. The raster attributes give a 1:1 pixel aspect ratio and the size.
+    write!(out, "\x1bPq\"1;1;{};{}", width, height)?;
+
+    // the palette, with each channel as a percentage.
+    for (index, color) in colors.iter().enumerate() {
+        let [r, g, b] = color.map(|channel| (channel as u32 * 100 + 127) / 255);
+        write!(out, "#{};2;{};{};{}", index, r, g, b)?;
+    }
+
+    for band in indexes.chunks(6) {
+        // the bits of every column's sixel, per colour used in the band.
+        let mut sixels: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for (bit, row) in band.iter().enumerate() {
+            for (pixel_x, &index) in row.iter().enumerate() {
+                sixels.entry(index).or_insert_with(|| vec![0; width])[pixel_x] |= 1 << bit;
+            }
+        }
+
+        for (index, bits) in &sixels {
+            write!(out, "#{}", index)?;
+            write_sixel_row(out, bits)?;
+            // '$' goes back to the start of the band, so the next colour is drawn over it.
+            out.write_all(b"$")?;
+        }
+        // '-' moves down to the next band.
+        out.write_all(b"-")?;
+    }
+
+    // ST ends the sixel data.
+    out.write_all(b"\x1b\\")?;
+    Ok(())
+}
+
+/*
+Writes one colour's sixels for a band. Each sixel is the character 63 ('?') plus its bits,
+with the top pixel in the lowest bit. Runs of the same sixel are shortened to !<count><sixel>.
+Trailing empty sixels are left out, since nothing needs to be drawn there.
+*/
+fn write_sixel_row<W: Write>(out: &mut W, bits: &[u8]) -> io::Result<()> {
+    let used = bits
+        .iter()
+        .rposition(|&sixel| sixel != 0)
+        .map_or(0, |last| last + 1);
+    let mut rest = &bits[..used];
+    while let Some(&sixel) = rest.first() {
+        let run = rest.iter().take_while(|&&other| other == sixel).count();
+        let character = (63 + sixel) as char;
+        if run > 3 {
+            write!(out, "!{}{}", run, character)?;
+        } else {
+            for _ in 0..run {
+                write!(out, "{}", character)?;
+            }
+        }
+        rest = &rest[run..];
+    }
+    Ok(())
+}
+
+/*
+Reduces a set of colours to at most max_colors, with the median cut algorithm:
+
+  1. Start with one box holding every colour.
+  2. Take the box whose colours are spread furthest along any one channel.
+  3. Sort its colours along that channel, and split it in two where half of the pixels are on
+     either side.
+  4. Repeat until there are enough boxes, or no box can be split any more.
+
+Each box then becomes one palette colour: the average of its colours, weighted by how many
+pixels have them. If there are few enough colours to begin with, they are all kept as they are.
+*/
+pub(crate) fn median_cut(histogram: &[([u8; 3], usize)], max_colors: usize) -> Vec<[u8; 3]> {
+    if histogram.len() <= max_colors {
+        return histogram.iter().map(|&(color, _)| color).collect();
+    }
+
+    let mut boxes: Vec<Vec<([u8; 3], usize)>> = vec![histogram.to_vec()];
+    while boxes.len() < max_colors {
+        // the widest box, and its widest channel.
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, spread) = (0..3)
+                    .map(|channel| {
+                        let values = colors.iter().map(|(color, _)| color[channel]);
+                        let spread = values.clone().max().unwrap() - values.min().unwrap();
+                        (channel, spread)
+                    })
+                    .max_by_key(|&(_, spread)| spread)
+                    .unwrap();
+                (index, channel, spread)
+            })
+            .max_by_key(|&(_, _, spread)| spread);
+        let (index, channel) = match widest {
+            Some((index, channel, _)) => (index, channel),
+            None => break,
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(color, _)| color[channel]);
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = 1;
+        for (position, (_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if seen * 2 >= total {
+                split = position + 1;
+                break;
+            }
+        }
+        // both halves need at least one colour.
+        let split = split.clamp(1, colors.len() - 1);
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette: Vec<[u8; 3]> = boxes
+        .iter()
+        .map(|colors| {
+            let total: usize = colors.iter().map(|(_, count)| count).sum();
+            let mut sums = [0usize; 3];
+            for (color, count) in colors {
+                for channel in 0..3 {
+                    sums[channel] += color[channel] as usize * count;
+                }
+            }
+            sums.map(|sum| ((sum + total / 2) / total) as u8)
+        })
+        .collect();
+    // the order of the boxes depends on how they were split. Sorting keeps the output stable.
+    palette.sort_unstable();
+    palette.dedup();
+    palette
+}
+
+fn nearest_color(colors: &[[u8; 3]], color: [u8; 3]) -> usize {
+    (0..colors.len())
+        .min_by_key(|&index| {
+            (0..3)
+                .map(|channel| {
+                    let difference = colors[index][channel] as i32 - color[channel] as i32;
+                    difference * difference
+                })
+                .sum::<i32>()
+        })
+        .unwrap_or(0)
+}
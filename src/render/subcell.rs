@@ -0,0 +1,106 @@
+//! Terminal output with more than one grid point per character cell.
+//!
+//! A terminal cell is about twice as tall as it is wide. The half-block renderer splits each cell
+//! into a top and a bottom pixel, drawn with the upper half block glyph in one colour on a
+//! background of the other. The braille renderer uses the 2x4 dots of the braille glyphs, which
+//! gives eight pixels per cell, but each of them can only be on or off.
+
+use std::io::{self, Write};
+
+use super::ansi::{ColorDepth, RESET};
+use crate::mandelbrot::EscapeValue;
+use crate::palette::Palette;
+use crate::scale::Scale;
+
+const UPPER_HALF_BLOCK: char = '▀';
+
+// the first braille glyph, with no dots raised. The other 255 follow it, one bit per dot.
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/*
+The bit for each dot of a braille cell, by [row][column]. The numbering is historical:
+dots 1-6 were the original 2x3 cell, and 7 and 8 were added below them later.
+
+  1 4      0x01 0x08
+  2 5      0x02 0x10
+  3 6      0x04 0x20
+  7 8      0x40 0x80
+*/
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Writes the grid with two grid rows per line of text, each cell showing the upper grid point
+/// as its foreground and the lower one as its background.
+///
+/// With an odd number of rows, the bottom half of the last line is left in the terminal's own
+/// background colour.
+pub fn render_half_blocks<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    palette: Palette,
+    depth: ColorDepth,
+    out: &mut W,
+) -> io::Result<()> {
+    for rows in mandelbrot_points.chunks(2) {
+        let mut line = String::new();
+
+        // only write a new escape sequence when a colour actually changes.
+        let mut current = (None, None);
+        for (pixel_x, &top) in rows[0].iter().enumerate() {
+            let foreground = depth.foreground(palette.color_iterations(top, max_iterations));
+            let background = match rows.get(1) {
+                Some(bottom) => {
+                    depth.background(palette.color_iterations(bottom[pixel_x], max_iterations))
+                }
+                // 49 switches back to the default background.
+                None => "\x1b[49m".to_string(),
+            };
+
+            if current.0.as_ref() != Some(&foreground) {
+                line.push_str(&foreground);
+            }
+            if current.1.as_ref() != Some(&background) {
+                line.push_str(&background);
+            }
+            current = (Some(foreground), Some(background));
+            line.push(UPPER_HALF_BLOCK);
+        }
+        writeln!(out, "{}{}", line, RESET)?;
+    }
+    Ok(())
+}
+
+/// Writes the grid as braille glyphs, with a 2x4 block of grid points per character.
+///
+/// A dot is raised where `scale` puts the grid point at `threshold` or higher on a ramp from
+/// 0.0 to 1.0. The inside of the set is at 1.0, so a threshold of 1.0 draws just the set itself,
+/// and lower thresholds add more and more of the area around it.
+pub fn render_braille<V: EscapeValue, W: Write>(
+    mandelbrot_points: &[Vec<V>],
+    max_iterations: usize,
+    scale: Scale,
+    threshold: f64,
+    out: &mut W,
+) -> io::Result<()> {
+    let scaler = scale.prepare(mandelbrot_points, max_iterations);
+    let width = mandelbrot_points.first().map_or(0, |row| row.len());
+
+    for rows in mandelbrot_points.chunks(4) {
+        let mut line = String::with_capacity(width.div_ceil(2) * 3);
+        for cell_x in 0..width.div_ceil(2) {
+            let mut bits = 0;
+            for (row, dots) in rows.iter().zip(BRAILLE_DOTS) {
+                for (dot_x, dot) in dots.into_iter().enumerate() {
+                    let raised = row
+                        .get(cell_x * 2 + dot_x)
+                        .is_some_and(|&pixel| scaler.map(pixel) >= threshold);
+                    if raised {
+                        bits |= dot;
+                    }
+                }
+            }
+            line.push(char::from_u32(BRAILLE_BLANK + bits).unwrap());
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
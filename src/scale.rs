@@ -0,0 +1,101 @@
+//! Maps escape values onto `0.0..1.0`, so the glyph and colour ramps follow `max_iterations`
+//! instead of fixed iteration counts.
+
+use crate::mandelbrot::EscapeValue;
+
+/// How the iteration counts of the escaped points are spread over a ramp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scale {
+    /// Evenly: a point that took half of `max_iterations` to escape lands halfway up the ramp.
+    Linear,
+    /// Logarithmically, which gives more of the ramp to the low counts far away from the set.
+    #[default]
+    Logarithmic,
+    /// By histogram equalization: every step of the ramp gets about as many pixels as the others,
+    /// whatever part of the set is on screen.
+    Histogram,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 3] = [Scale::Linear, Scale::Logarithmic, Scale::Histogram];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scale::Linear => "linear",
+            Scale::Logarithmic => "log",
+            Scale::Histogram => "histogram",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scale> {
+        Scale::ALL.into_iter().find(|scale| scale.name() == name)
+    }
+
+    /// Prepares the scale for a grid. Only [`Scale::Histogram`] looks at the values in it.
+    pub fn prepare<V: EscapeValue>(
+        self,
+        mandelbrot_points: &[Vec<V>],
+        max_iterations: usize,
+    ) -> Scaler {
+        let cumulative = match self {
+            Scale::Histogram => histogram(mandelbrot_points, max_iterations),
+            _ => Vec::new(),
+        };
+        Scaler {
+            scale: self,
+            max_iterations,
+            cumulative,
+        }
+    }
+}
+
+/// A [`Scale`] that is ready to map the values of one grid.
+#[derive(Debug, Clone)]
+pub struct Scaler {
+    scale: Scale,
+    max_iterations: usize,
+    // for the histogram scale: how many escaped pixels took fewer than n iterations, at index n,
+    // with the total number of escaped pixels at the end.
+    cumulative: Vec<usize>,
+}
+
+impl Scaler {
+    /// Where `value` lies on the ramp: from 0.0 for points that escaped right away, up to just
+    /// below 1.0 for the points that took the longest. Points inside the set map to 1.0.
+    pub fn map<V: EscapeValue>(&self, value: V) -> f64 {
+        let value = value.to_f64();
+        let max = self.max_iterations as f64;
+        if value >= max {
+            return 1.0;
+        }
+
+        let t = match self.scale {
+            Scale::Linear => value / max,
+            Scale::Logarithmic => (1.0 + value).ln() / (1.0 + max).ln(),
+            Scale::Histogram => {
+                let total = *self.cumulative.last().unwrap_or(&0);
+                if total == 0 {
+                    return 0.0;
+                }
+                self.cumulative[value as usize] as f64 / total as f64
+            }
+        };
+        // keep escaped points off 1.0, which is reserved for the inside of the set.
+        t.clamp(0.0, 1.0 - f64::EPSILON)
+    }
+}
+
+// counts the escaped pixels per whole iteration count, and sums the counts up.
+fn histogram<V: EscapeValue>(mandelbrot_points: &[Vec<V>], max_iterations: usize) -> Vec<usize> {
+    let mut counts = vec![0; max_iterations + 1];
+    for &value in mandelbrot_points.iter().flatten() {
+        let value = value.to_f64();
+        if value < max_iterations as f64 {
+            counts[value as usize + 1] += 1;
+        }
+    }
+    for n in 1..counts.len() {
+        counts[n] += counts[n - 1];
+    }
+    counts
+}
@@ -0,0 +1,570 @@
+//! Scene files: everything needed to render the same picture again, in a small TOML file.
+//!
+//! ```toml
+//! version = 1
+//!
+//! [view]
+//! real_min = -2.0
+//! real_max = 1.0
+//! imaginary_min = -1.0
+//! imaginary_max = 1.0
+//! width = 230
+//! height = 66
+//!
+//! [iteration]
+//! max_iterations = 1000
+//! # a built-in formula, or one typed in such as "z^3 - z + c" (see the expression module)
+//! formula = "mandelbrot"
+//! # only for formula = "multibrot": the power d of z^d + c
+//! # power = 3.0
+//! bailout_radius = 2.0
+//! bailout_norm = "euclidean"
+//! interior_shortcut = true
+//! periodicity_check = true
+//! periodicity_tolerance = 1e-12
+//! # only for a Julia set: the constant c, as in --julia
+//! # julia_real = -0.123
+//! # julia_imaginary = 0.745
+//!
+//! # only for a Newton fractal, instead of the formula: the roots of its polynomial, as in --roots
+//! # [newton]
+//! # roots = "1.0 -0.5,0.8660254037844386 -0.5,-0.8660254037844386"
+//! # relaxation_real = 1.0
+//! # relaxation_imaginary = 0.0
+//! # nova = false
+//! # tolerance = 1e-6
+//! # only for a Buddhabrot: how many random orbits to trace, and which of them to count
+//! # [buddhabrot]
+//! # samples = 1000000
+//! # seed = 1
+//! # anti = false
+//! # only for a Nebulabrot: the iterations of each channel
+//! # red_iterations = 5000
+//! # green_iterations = 500
+//! # blue_iterations = 50
+//!
+//! [coloring]
+//! palette = "ocean"
+//! scale = "log"
+//! smooth = false
+//! periods = false
+//! ramp = "¸.•›-˛˙˛‘¨¸ "
+//! threshold = 1.0
+//!
+//! [output]
+//! mode = "png"
+//! path = "mandelbrot.png"
+//! ```
+//!
+//! The view is stored as its exact bounds, not as a [`Location`](crate::Location), so that the
+//! scene gives back the same pixels whatever terminal or cell shape it is loaded in. Keys that are
+//! left out take their default values, and unknown keys are an error, so a typo is not silently
+//! ignored.
+//!
+//! PNG images carry their scene too, in a text chunk named [`PNG_KEYWORD`], and
+//! [`Scene::load`] reads it from them as it would from a scene file.
+
+mod toml;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use num::complex::Complex;
+
+use crate::bailout::Norm;
+use crate::buddhabrot::Buddhabrot;
+use crate::formula::Fractal;
+use crate::newton::{self, Newton};
+use crate::palette::Palette;
+use crate::render::ascii::Ramp;
+use crate::render::png;
+use crate::scale::Scale;
+use crate::view::Config;
+
+use self::toml::{Document, Value};
+
+/// The version written to new scene files. Files with a higher version were made by a newer
+/// program, and are refused instead of being half understood.
+pub const SCENE_VERSION: i64 = 1;
+
+/// The keyword of the PNG text chunk that holds the scene of an image.
+pub const PNG_KEYWORD: &str = "rusty-mandelbrot scene";
+
+/// A complete description of a render.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Scene {
+    /// The view, iterations, bailout and the other settings of the escape-time loop.
+    pub config: Config,
+    /// For a Newton fractal, which takes the place of the formula in `config`.
+    pub newton: Option<Newton>,
+    /// For a Buddhabrot of the formula in `config`.
+    pub buddhabrot: Option<Buddhabrot>,
+    pub coloring: Coloring,
+    pub output: Output,
+}
+
+/// How the escape values are turned into characters or colours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coloring {
+    pub palette: Palette,
+    pub scale: Scale,
+    /// Colour by the fractional iteration count.
+    pub smooth: bool,
+    /// Colour the inside of the set by the period of each orbit.
+    pub periods: bool,
+    pub ramp: Ramp,
+    /// For braille output: how far up the scale a point has to be for its dot to be drawn.
+    pub threshold: f64,
+}
+
+impl Default for Coloring {
+    fn default() -> Coloring {
+        Coloring {
+            palette: Palette::default(),
+            scale: Scale::default(),
+            smooth: false,
+            periods: false,
+            ramp: Ramp::default(),
+            threshold: 1.0,
+        }
+    }
+}
+
+/// Where the render went: the name of an output mode of the binary (`ascii`, `png`, ...), and the
+/// file it wrote, for the modes that write one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub mode: String,
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Output {
+    fn default() -> Output {
+        Output {
+            mode: "ascii".to_string(),
+            path: None,
+        }
+    }
+}
+
+// every key a scene file may contain, by section.
+const KEYS: [(&str, &[&str]); 7] = [
+    ("", &["version"]),
+    (
+        "view",
+        &[
+            "real_min",
+            "real_max",
+            "imaginary_min",
+            "imaginary_max",
+            "width",
+            "height",
+        ],
+    ),
+    (
+        "iteration",
+        &[
+            "max_iterations",
+            "formula",
+            "power",
+            "bailout_radius",
+            "bailout_norm",
+            "interior_shortcut",
+            "periodicity_check",
+            "periodicity_tolerance",
+            "julia_real",
+            "julia_imaginary",
+        ],
+    ),
+    (
+        "newton",
+        &[
+            "roots",
+            "relaxation_real",
+            "relaxation_imaginary",
+            "nova",
+            "tolerance",
+        ],
+    ),
+    (
+        "buddhabrot",
+        &[
+            "samples",
+            "seed",
+            "anti",
+            "red_iterations",
+            "green_iterations",
+            "blue_iterations",
+        ],
+    ),
+    (
+        "coloring",
+        &["palette", "scale", "smooth", "periods", "ramp", "threshold"],
+    ),
+    ("output", &["mode", "path"]),
+];
+
+impl Scene {
+    /// Reads a scene file, or the scene stored in a PNG image (see [`Scene::from_png`]).
+    pub fn load(path: &Path) -> Result<Scene, String> {
+        let bytes = fs::read(path)
+            .map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+        let scene = if bytes.starts_with(&png::SIGNATURE) {
+            Scene::from_png(&bytes)
+        } else {
+            String::from_utf8(bytes)
+                .map_err(|_| "a scene file must be UTF-8 text".to_string())
+                .and_then(|text| Scene::from_toml(&text))
+        };
+        scene.map_err(|message| format!("{}: {}", path.display(), message))
+    }
+
+    /// Reads the scene from the text chunks of a PNG image.
+    ///
+    /// The output is left at its default, rather than pointing at the image itself, so that
+    /// rendering the scene again does not overwrite the image it came from.
+    pub fn from_png(bytes: &[u8]) -> Result<Scene, String> {
+        let text = png::read_text(bytes).map_err(|error| error.to_string())?;
+        let (_, toml) = text
+            .iter()
+            .find(|(keyword, _)| keyword == PNG_KEYWORD)
+            .ok_or("the image holds no scene")?;
+        Ok(Scene {
+            output: Output::default(),
+            ..Scene::from_toml(toml)?
+        })
+    }
+
+    /// Writes the scene to a file, replacing what was there.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_toml())
+            .map_err(|error| format!("could not write {}: {}", path.display(), error))
+    }
+
+    /// Where the scene of an image is written: next to it, with `.toml` instead of its extension.
+    pub fn path_next_to(image: &Path) -> PathBuf {
+        image.with_extension("toml")
+    }
+
+    pub fn to_toml(&self) -> String {
+        let config = &self.config;
+        let view = &config.view;
+        let coloring = &self.coloring;
+
+        let mut document = Document::default();
+        document.set("", "version", Value::Integer(SCENE_VERSION));
+
+        document.set("view", "real_min", Value::Float(view.real_min));
+        document.set("view", "real_max", Value::Float(view.real_max));
+        document.set("view", "imaginary_min", Value::Float(view.imaginary_min));
+        document.set("view", "imaginary_max", Value::Float(view.imaginary_max));
+        document.set("view", "width", Value::Integer(view.width as i64));
+        document.set("view", "height", Value::Integer(view.height as i64));
+
+        let mut iteration = vec![
+            (
+                "max_iterations",
+                Value::Integer(config.max_iterations as i64),
+            ),
+            ("formula", Value::String(config.formula.name().to_string())),
+            ("bailout_radius", Value::Float(config.bailout.radius)),
+            (
+                "bailout_norm",
+                Value::String(config.bailout.norm.name().to_string()),
+            ),
+            (
+                "interior_shortcut",
+                Value::Boolean(config.interior_shortcut),
+            ),
+            (
+                "periodicity_check",
+                Value::Boolean(config.periodicity_check),
+            ),
+            (
+                "periodicity_tolerance",
+                Value::Float(config.periodicity_tolerance),
+            ),
+        ];
+        // the power goes right after the formula it belongs to.
+        if let Some(power) = config.formula.power() {
+            iteration.insert(2, ("power", Value::Float(power)));
+        }
+        for (key, value) in iteration {
+            document.set("iteration", key, value);
+        }
+        if let Some(c) = config.julia {
+            document.set("iteration", "julia_real", Value::Float(c.re));
+            document.set("iteration", "julia_imaginary", Value::Float(c.im));
+        }
+
+        if let Some(newton) = &self.newton {
+            let settings = [
+                (
+                    "roots",
+                    Value::String(newton::format_numbers(newton.roots())),
+                ),
+                ("relaxation_real", Value::Float(newton.relaxation.re)),
+                ("relaxation_imaginary", Value::Float(newton.relaxation.im)),
+                ("nova", Value::Boolean(newton.nova)),
+                ("tolerance", Value::Float(newton.tolerance)),
+            ];
+            for (key, value) in settings {
+                document.set("newton", key, value);
+            }
+        }
+
+        if let Some(buddhabrot) = &self.buddhabrot {
+            let mut settings = vec![
+                ("samples", Value::Integer(buddhabrot.samples as i64)),
+                // all 64 bits of the seed, even those that make the integer negative.
+                ("seed", Value::Integer(buddhabrot.seed as i64)),
+                ("anti", Value::Boolean(buddhabrot.anti)),
+            ];
+            if let Some([red, green, blue]) = buddhabrot.nebula {
+                settings.push(("red_iterations", Value::Integer(red as i64)));
+                settings.push(("green_iterations", Value::Integer(green as i64)));
+                settings.push(("blue_iterations", Value::Integer(blue as i64)));
+            }
+            for (key, value) in settings {
+                document.set("buddhabrot", key, value);
+            }
+        }
+
+        let coloring = [
+            (
+                "palette",
+                Value::String(coloring.palette.name().to_string()),
+            ),
+            ("scale", Value::String(coloring.scale.name().to_string())),
+            ("smooth", Value::Boolean(coloring.smooth)),
+            ("periods", Value::Boolean(coloring.periods)),
+            ("ramp", Value::String(coloring.ramp.to_string())),
+            ("threshold", Value::Float(coloring.threshold)),
+        ];
+        for (key, value) in coloring {
+            document.set("coloring", key, value);
+        }
+
+        document.set("output", "mode", Value::String(self.output.mode.clone()));
+        if let Some(path) = &self.output.path {
+            document.set(
+                "output",
+                "path",
+                Value::String(path.to_string_lossy().into_owned()),
+            );
+        }
+
+        format!("# rusty-mandelbrot scene\n{}", document)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Scene, String> {
+        let document = Document::parse(text)?;
+        for (section, key) in document.keys() {
+            let known = KEYS
+                .iter()
+                .any(|(name, keys)| *name == section && keys.contains(&key));
+            if !known {
+                return Err(match section {
+                    "" => format!("unknown key '{}'", key),
+                    _ => format!("unknown key '{}' in [{}]", key, section),
+                });
+            }
+        }
+
+        let reader = Reader {
+            document: &document,
+        };
+        let version = reader
+            .integer("", "version")?
+            .ok_or("the scene has no version")?;
+        if !(1..=SCENE_VERSION).contains(&version) {
+            return Err(format!(
+                "scene version {} is not supported (this program reads up to version {})",
+                version, SCENE_VERSION
+            ));
+        }
+
+        let mut scene = Scene::default();
+        let config = &mut scene.config;
+        let view = &mut config.view;
+        reader.float("view", "real_min", &mut view.real_min)?;
+        reader.float("view", "real_max", &mut view.real_max)?;
+        reader.float("view", "imaginary_min", &mut view.imaginary_min)?;
+        reader.float("view", "imaginary_max", &mut view.imaginary_max)?;
+        reader.size("view", "width", &mut view.width)?;
+        reader.size("view", "height", &mut view.height)?;
+
+        reader.size("iteration", "max_iterations", &mut config.max_iterations)?;
+        if let Some(name) = reader.string("iteration", "formula")? {
+            config.formula = Fractal::parse(name)?;
+        }
+        // the power of a Multibrot set, which no other formula has.
+        match (&config.formula, reader.number("iteration", "power")?) {
+            (Fractal::Multibrot(_), Some(power)) => config.formula = Fractal::with_power(power)?,
+            (_, Some(_)) => return Err("'power' only goes with the multibrot formula".to_string()),
+            _ => {}
+        }
+        reader.float("iteration", "bailout_radius", &mut config.bailout.radius)?;
+        if let Some(name) = reader.string("iteration", "bailout_norm")? {
+            config.bailout.norm =
+                Norm::from_name(name).ok_or_else(|| format!("unknown norm '{}'", name))?;
+        }
+        reader.boolean(
+            "iteration",
+            "interior_shortcut",
+            &mut config.interior_shortcut,
+        )?;
+        reader.boolean(
+            "iteration",
+            "periodicity_check",
+            &mut config.periodicity_check,
+        )?;
+        reader.float(
+            "iteration",
+            "periodicity_tolerance",
+            &mut config.periodicity_tolerance,
+        )?;
+        // a Julia set needs both parts of its constant, the Mandelbrot set neither.
+        config.julia = match (
+            reader.number("iteration", "julia_real")?,
+            reader.number("iteration", "julia_imaginary")?,
+        ) {
+            (None, None) => None,
+            (Some(re), Some(im)) => Some(Complex::new(re, im)),
+            _ => return Err("'julia_real' and 'julia_imaginary' go together".to_string()),
+        };
+        config.validate()?;
+
+        // the roots make it a Newton fractal, and the other keys of the section need them.
+        scene.newton = match reader.string("newton", "roots")? {
+            Some(roots) => {
+                let mut newton = Newton::from_roots(&newton::parse_numbers(roots)?)?;
+                reader.float("newton", "relaxation_real", &mut newton.relaxation.re)?;
+                reader.float("newton", "relaxation_imaginary", &mut newton.relaxation.im)?;
+                reader.boolean("newton", "nova", &mut newton.nova)?;
+                reader.float("newton", "tolerance", &mut newton.tolerance)?;
+                newton.validate()?;
+                Some(newton)
+            }
+            None if document.keys().any(|(section, _)| section == "newton") => {
+                return Err("the [newton] section needs 'roots'".to_string())
+            }
+            None => None,
+        };
+
+        // any of its keys makes it a Buddhabrot, as a section without keys is not written.
+        if document.keys().any(|(section, _)| section == "buddhabrot") {
+            let mut buddhabrot = Buddhabrot::default();
+            reader.size("buddhabrot", "samples", &mut buddhabrot.samples)?;
+            if let Some(seed) = reader.integer("buddhabrot", "seed")? {
+                buddhabrot.seed = seed as u64;
+            }
+            reader.boolean("buddhabrot", "anti", &mut buddhabrot.anti)?;
+            let mut limits = [0; 3];
+            let keys = ["red_iterations", "green_iterations", "blue_iterations"];
+            let given = keys
+                .iter()
+                .filter(|key| reader.document.get("buddhabrot", key).is_some())
+                .count();
+            for (limit, key) in limits.iter_mut().zip(keys) {
+                reader.size("buddhabrot", key, limit)?;
+            }
+            buddhabrot.nebula = match given {
+                0 => None,
+                3 => Some(limits),
+                _ => return Err("the iterations of all three channels go together".to_string()),
+            };
+            if scene.newton.is_some() {
+                return Err("a scene has either [newton] or [buddhabrot], not both".to_string());
+            }
+            buddhabrot.validate()?;
+            scene.buddhabrot = Some(buddhabrot);
+        }
+
+        let coloring = &mut scene.coloring;
+        if let Some(name) = reader.string("coloring", "palette")? {
+            coloring.palette =
+                Palette::from_name(name).ok_or_else(|| format!("unknown palette '{}'", name))?;
+        }
+        if let Some(name) = reader.string("coloring", "scale")? {
+            coloring.scale =
+                Scale::from_name(name).ok_or_else(|| format!("unknown scale '{}'", name))?;
+        }
+        reader.boolean("coloring", "smooth", &mut coloring.smooth)?;
+        reader.boolean("coloring", "periods", &mut coloring.periods)?;
+        if let Some(glyphs) = reader.string("coloring", "ramp")? {
+            coloring.ramp = Ramp::new(glyphs)?;
+        }
+        reader.float("coloring", "threshold", &mut coloring.threshold)?;
+
+        if let Some(mode) = reader.string("output", "mode")? {
+            scene.output.mode = mode.to_string();
+        }
+        scene.output.path = reader.string("output", "path")?.map(PathBuf::from);
+        Ok(scene)
+    }
+}
+
+// reads typed values out of a document, leaving the target alone when the key is missing.
+struct Reader<'a> {
+    document: &'a Document,
+}
+
+impl<'a> Reader<'a> {
+    fn wrong_type(section: &str, key: &str, expected: &str) -> String {
+        match section {
+            "" => format!("'{}' must be {}", key, expected),
+            _ => format!("'{}' in [{}] must be {}", key, section, expected),
+        }
+    }
+
+    fn string(&self, section: &str, key: &str) -> Result<Option<&'a str>, String> {
+        match self.document.get(section, key) {
+            None => Ok(None),
+            Some(Value::String(text)) => Ok(Some(text)),
+            Some(_) => Err(Reader::wrong_type(section, key, "a string")),
+        }
+    }
+
+    fn integer(&self, section: &str, key: &str) -> Result<Option<i64>, String> {
+        match self.document.get(section, key) {
+            None => Ok(None),
+            Some(Value::Integer(number)) => Ok(Some(*number)),
+            Some(_) => Err(Reader::wrong_type(section, key, "an integer")),
+        }
+    }
+
+    fn size(&self, section: &str, key: &str, target: &mut usize) -> Result<(), String> {
+        if let Some(number) = self.integer(section, key)? {
+            *target = usize::try_from(number)
+                .map_err(|_| Reader::wrong_type(section, key, "0 or more"))?;
+        }
+        Ok(())
+    }
+
+    // integers are accepted too, since "2" is as good a radius as "2.0".
+    fn number(&self, section: &str, key: &str) -> Result<Option<f64>, String> {
+        match self.document.get(section, key) {
+            None => Ok(None),
+            Some(Value::Float(number)) => Ok(Some(*number)),
+            Some(Value::Integer(number)) => Ok(Some(*number as f64)),
+            Some(_) => Err(Reader::wrong_type(section, key, "a number")),
+        }
+    }
+
+    fn float(&self, section: &str, key: &str, target: &mut f64) -> Result<(), String> {
+        if let Some(number) = self.number(section, key)? {
+            *target = number;
+        }
+        Ok(())
+    }
+
+    fn boolean(&self, section: &str, key: &str, target: &mut bool) -> Result<(), String> {
+        match self.document.get(section, key) {
+            None => {}
+            Some(Value::Boolean(flag)) => *target = *flag,
+            Some(_) => return Err(Reader::wrong_type(section, key, "true or false")),
+        }
+        Ok(())
+    }
+}
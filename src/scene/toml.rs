@@ -0,0 +1,234 @@
+/*
+Just enough TOML to read and write scene files, so the crate does not need a parser dependency.
+
+Supported: comments (#), [section] headers, and key = value pairs whose value is a "string",
+an integer, a float or a boolean. Arrays, inline tables, dates and multi-line strings are not,
+since scene files never contain them. Anything else is rejected with the line number, rather
+than being misread.
+*/
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(text) => write_string(f, text),
+            Value::Integer(number) => write!(f, "{}", number),
+            // Debug always writes a '.' or an exponent (1.0, not 1), so it reads back as a float,
+            // and like Display it is the shortest form that parses back to the same number.
+            Value::Float(number) => write!(f, "{:?}", number),
+            Value::Boolean(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+// a basic string, with quotes, backslashes and control characters escaped.
+fn write_string(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for character in text.chars() {
+        match character {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            _ if character.is_control() => write!(f, "\\u{:04X}", character as u32)?,
+            _ => write!(f, "{}", character)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// A parsed file: the keys before the first header go in the section named "".
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Document {
+    sections: Vec<(String, Vec<(String, Value)>)>,
+}
+
+impl Document {
+    pub(crate) fn parse(text: &str) -> Result<Document, String> {
+        let mut document = Document::default();
+        let mut section = String::new();
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {}", index + 1, message);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("expected ']' at the end of the section header"))?;
+                section = name.trim().to_string();
+                if !is_bare_key(&section) {
+                    return Err(error(&format!("invalid section name '{}'", section)));
+                }
+                if document.sections.iter().any(|(other, _)| *other == section) {
+                    return Err(error(&format!("section [{}] appears twice", section)));
+                }
+                document.sections.push((section.clone(), Vec::new()));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected 'key = value'"))?;
+            let key = key.trim();
+            if !is_bare_key(key) {
+                return Err(error(&format!("invalid key '{}'", key)));
+            }
+            let value = parse_value(value.trim()).map_err(|message| error(&message))?;
+            if document.get(&section, key).is_some() {
+                return Err(error(&format!("key '{}' appears twice", key)));
+            }
+            document.set(&section, key, value);
+        }
+        Ok(document)
+    }
+
+    pub(crate) fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections
+            .iter()
+            .find(|(name, _)| name == section)?
+            .1
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets a key, adding the section after the others if it is new.
+    pub(crate) fn set(&mut self, section: &str, key: &str, value: Value) {
+        let index = match self.sections.iter().position(|(name, _)| name == section) {
+            Some(index) => index,
+            None => {
+                self.sections.push((section.to_string(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        let entries = &mut self.sections[index].1;
+        match entries.iter_mut().find(|(name, _)| name == key) {
+            Some((_, existing)) => *existing = value,
+            None => entries.push((key.to_string(), value)),
+        }
+    }
+
+    /// Every key of every section, to check them against the ones that are known.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.sections.iter().flat_map(|(section, entries)| {
+            entries
+                .iter()
+                .map(move |(key, _)| (section.as_str(), key.as_str()))
+        })
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (section, entries)) in self.sections.iter().enumerate() {
+            if !section.is_empty() {
+                if index > 0 {
+                    writeln!(f)?;
+                }
+                writeln!(f, "[{}]", section)?;
+            }
+            for (key, value) in entries {
+                writeln!(f, "{} = {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// bare keys are made of ASCII letters, digits, '_' and '-'.
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '_' || character == '-'
+        })
+}
+
+// cuts the line at the first '#' that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if let Some(quoted) = text.strip_prefix('"') {
+        return parse_string(quoted).map(Value::String);
+    }
+    match text {
+        "true" => return Ok(Value::Boolean(true)),
+        "false" => return Ok(Value::Boolean(false)),
+        _ => {}
+    }
+
+    // TOML allows '_' between digits, as in 1_000_000.
+    let number = text.replace('_', "");
+    if let Ok(integer) = number.parse::<i64>() {
+        return Ok(Value::Integer(integer));
+    }
+    let is_float = number
+        .chars()
+        .all(|character| character.is_ascii_digit() || "+-.eE".contains(character));
+    match number.parse::<f64>() {
+        Ok(float) if is_float => Ok(Value::Float(float)),
+        _ => Err(format!("invalid value '{}'", text)),
+    }
+}
+
+// parses the rest of a basic string, after its opening quote.
+fn parse_string(text: &str) -> Result<String, String> {
+    let mut parsed = String::new();
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' => {
+                return match characters.as_str().trim() {
+                    "" => Ok(parsed),
+                    rest => Err(format!("unexpected '{}' after the string", rest)),
+                }
+            }
+            '\\' => {
+                let escape = characters.next().ok_or("unfinished escape in string")?;
+                match escape {
+                    '"' => parsed.push('"'),
+                    '\\' => parsed.push('\\'),
+                    'n' => parsed.push('\n'),
+                    't' => parsed.push('\t'),
+                    'r' => parsed.push('\r'),
+                    'u' => {
+                        let hex: String = characters.by_ref().take(4).collect();
+                        let unicode = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape '\\u{}'", hex))?;
+                        parsed.push(unicode);
+                    }
+                    _ => return Err(format!("invalid escape '\\{}'", escape)),
+                }
+            }
+            _ => parsed.push(character),
+        }
+    }
+    Err("missing '\"' at the end of the string".to_string())
+}
@@ -0,0 +1,230 @@
+//! The terminal the program is running in: its size, and raw keyboard input for interactive use.
+//!
+//! The size is asked from the terminal itself with the `TIOCGWINSZ` ioctl. When that is not
+//! possible (output piped to a file, or not a unix system) the `COLUMNS` and `LINES` environment
+//! variables are used instead, which most shells set.
+//!
+//! Raw mode and reading input are only available on unix systems, where they go through termios.
+
+use std::env;
+use std::io;
+
+/// How much taller than wide a character cell is, for when the terminal does not report its size
+/// in pixels. Most terminal fonts are close to this.
+pub const DEFAULT_CELL_ASPECT: f64 = 2.0;
+
+/// The size of the terminal, in character cells and, if the terminal reports it, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub columns: usize,
+    pub rows: usize,
+    /// The size of the text area in pixels, or 0 when it is not known.
+    pub pixel_width: usize,
+    pub pixel_height: usize,
+}
+
+impl TerminalSize {
+    /// Asks the terminal for its size, falling back to `COLUMNS` and `LINES`.
+    /// Returns `None` if neither gives an answer.
+    pub fn detect() -> Option<TerminalSize> {
+        ioctl_size().or_else(env_size)
+    }
+
+    /// The height of a character cell divided by its width, measured from the pixel size when
+    /// the terminal reports it, or [`DEFAULT_CELL_ASPECT`] otherwise.
+    pub fn cell_aspect(&self) -> f64 {
+        if self.pixel_width == 0 || self.pixel_height == 0 {
+            return DEFAULT_CELL_ASPECT;
+        }
+        let cell_width = self.pixel_width as f64 / self.columns as f64;
+        let cell_height = self.pixel_height as f64 / self.rows as f64;
+        cell_height / cell_width
+    }
+}
+
+#[cfg(unix)]
+fn ioctl_size() -> Option<TerminalSize> {
+    // stdout may be piped while stderr or stdin still belong to the terminal, so try all three.
+    [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = libc::winsize {
+                ws_row: 0,
+                ws_col: 0,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+            // SAFETY: TIOCGWINSZ only writes a winsize into the struct it is given.
+            let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+            if result != 0 || size.ws_col == 0 || size.ws_row == 0 {
+                return None;
+            }
+            Some(TerminalSize {
+                columns: size.ws_col as usize,
+                rows: size.ws_row as usize,
+                pixel_width: size.ws_xpixel as usize,
+                pixel_height: size.ws_ypixel as usize,
+            })
+        })
+}
+
+#[cfg(not(unix))]
+fn ioctl_size() -> Option<TerminalSize> {
+    None
+}
+
+fn env_size() -> Option<TerminalSize> {
+    parse_size(&env::var("COLUMNS").ok()?, &env::var("LINES").ok()?)
+}
+
+// a size from the text of COLUMNS and LINES, which have to be whole numbers above 0.
+fn parse_size(columns: &str, rows: &str) -> Option<TerminalSize> {
+    let read = |text: &str| text.trim().parse::<usize>().ok().filter(|&value| value > 0);
+    Some(TerminalSize {
+        columns: read(columns)?,
+        rows: read(rows)?,
+        pixel_width: 0,
+        pixel_height: 0,
+    })
+}
+
+/// Keeps the terminal in raw mode for as long as it lives, and puts the old settings back when
+/// dropped.
+///
+/// In raw mode every key press can be read as soon as it happens, without waiting for enter,
+/// keys are not echoed, and Ctrl-C arrives as a byte (3) instead of stopping the program.
+pub struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Switches stdin's terminal to raw mode. Fails when stdin is not a terminal.
+    #[cfg(unix)]
+    pub fn enable() -> io::Result<RawMode> {
+        // SAFETY: termios is plain data, and tcgetattr fills it in completely before it is read.
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        // SAFETY: cfmakeraw only changes the flags of the termios it is given.
+        unsafe { libc::cfmakeraw(&mut raw) };
+        // cfmakeraw also turns off output processing, but "\n" should still start a new line.
+        raw.c_oflag |= libc::OPOST;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { original })
+    }
+
+    #[cfg(not(unix))]
+    pub fn enable() -> io::Result<RawMode> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "raw terminal input needs a unix system",
+        ))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores the settings read in enable. Nothing can be done if it fails.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Waits up to `timeout_ms` milliseconds for input on stdin, and tells whether there is some.
+/// A timeout of 0 only checks, without waiting.
+#[cfg(unix)]
+pub fn poll_input(timeout_ms: i32) -> io::Result<bool> {
+    let mut stdin = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: poll is given exactly one pollfd, which lives on the stack for the whole call.
+    let ready = unsafe { libc::poll(&mut stdin, 1, timeout_ms) };
+    match ready {
+        -1 => {
+            let error = io::Error::last_os_error();
+            // a signal (like the one for a resized window) interrupting the wait is not an error.
+            if error.kind() == io::ErrorKind::Interrupted {
+                Ok(false)
+            } else {
+                Err(error)
+            }
+        }
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn poll_input(_timeout_ms: i32) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Reads whatever input is waiting on stdin, straight from the file descriptor.
+///
+/// `std::io::stdin` is not used, since it buffers: bytes sitting in its buffer would not wake up
+/// [`poll_input`], and key presses would seem to go missing until the next one.
+#[cfg(unix)]
+pub fn read_input(buffer: &mut [u8]) -> io::Result<usize> {
+    // SAFETY: read writes at most buffer.len() bytes into the buffer.
+    let read = unsafe {
+        libc::read(
+            libc::STDIN_FILENO,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len(),
+        )
+    };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(read as usize)
+}
+
+#[cfg(not(unix))]
+pub fn read_input(_buffer: &mut [u8]) -> io::Result<usize> {
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_from_the_environment() {
+        let size = parse_size("120", " 40\n").unwrap();
+        assert_eq!((size.columns, size.rows), (120, 40));
+        // the pixel size is not known, so the cells get the default shape.
+        assert_eq!(size.cell_aspect(), DEFAULT_CELL_ASPECT);
+
+        for (columns, rows) in [
+            ("0", "40"),
+            ("120", "0"),
+            ("", "40"),
+            ("wide", "40"),
+            ("-5", "40"),
+            ("80.5", "24"),
+        ] {
+            assert_eq!(parse_size(columns, rows), None, "{:?} {:?}", columns, rows);
+        }
+    }
+
+    #[test]
+    fn cell_aspect_from_pixels() {
+        let size = TerminalSize {
+            columns: 100,
+            rows: 50,
+            pixel_width: 800,
+            pixel_height: 900,
+        };
+        // cells of 8 by 18 pixels.
+        assert_eq!(size.cell_aspect(), 2.25);
+    }
+}
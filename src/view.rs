@@ -0,0 +1,235 @@
+use num::complex::Complex;
+
+use crate::bailout::Bailout;
+use crate::formula::Fractal;
+
+/// The part of the complex plane that is rendered, and the number of pixels it is divided into.
+///
+/// The complex plane is specified by `real_min`, `real_max`, `imaginary_min` and `imaginary_max`.
+/// X-axis: `real_min` and `real_max`. Y-axis: `imaginary_min` and `imaginary_max`.
+/// Pixel row 0 lies on `imaginary_min`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub real_min: f64,
+    pub real_max: f64,
+    pub imaginary_min: f64,
+    pub imaginary_max: f64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl View {
+    pub fn new(
+        real_min: f64,
+        real_max: f64,
+        imaginary_min: f64,
+        imaginary_max: f64,
+        width: usize,
+        height: usize,
+    ) -> View {
+        View {
+            real_min,
+            real_max,
+            imaginary_min,
+            imaginary_max,
+            width,
+            height,
+        }
+    }
+
+    /// Builds a view around `center`. Zoom 1 shows exactly as much of the plane as
+    /// [`View::default`], zoom 2 shows half of it, and so on.
+    pub fn from_center_zoom(center: Complex<f64>, zoom: f64, width: usize, height: usize) -> View {
+        let default = View::default();
+        let half_width = (default.real_max - default.real_min) / zoom / 2.0;
+        let half_height = (default.imaginary_max - default.imaginary_min) / zoom / 2.0;
+        View::new(
+            center.re - half_width,
+            center.re + half_width,
+            center.im - half_height,
+            center.im + half_height,
+            width,
+            height,
+        )
+    }
+
+    /// Like [`View::from_center_zoom`], but keeps circles round on screen when a point is not
+    /// drawn as a square. `point_aspect` is the height of one point divided by its width: 1.0 for
+    /// image pixels, about 2.0 for a terminal character cell.
+    ///
+    /// At zoom 1 the whole of [`View::default`] fits in the view, with extra room along one axis
+    /// if the shape of the screen differs from it.
+    pub fn from_center_zoom_aspect(
+        center: Complex<f64>,
+        zoom: f64,
+        width: usize,
+        height: usize,
+        point_aspect: f64,
+    ) -> View {
+        let default = View::default();
+        // the size of the view on screen, with the width of one point as the unit.
+        let screen_width = width as f64;
+        let screen_height = height as f64 * point_aspect;
+
+        /*
+        How many screen units one unit of the complex plane takes up, in both directions.
+        The smaller of the two lets the default view fit both horizontally and vertically.
+          Example:
+            // 100x30 cells that are twice as tall as they are wide: 100 wide, 60 high on screen.
+            horizontal = 100 / 3 = 33.3
+            vertical   =  60 / 2 = 30.0
+            // the height decides: the view is 2.0 high and 100 / 30 = 3.33 wide.
+        */
+        let horizontal = screen_width / (default.real_max - default.real_min);
+        let vertical = screen_height / (default.imaginary_max - default.imaginary_min);
+        let units = horizontal.min(vertical) * zoom;
+
+        let half_width = screen_width / units / 2.0;
+        let half_height = screen_height / units / 2.0;
+        View::new(
+            center.re - half_width,
+            center.re + half_width,
+            center.im - half_height,
+            center.im + half_height,
+            width,
+            height,
+        )
+    }
+
+    /// Checks that the view has pixels in it and that every min is less than its max.
+    ///
+    /// A very deep zoom can run out of f64 precision, so that min and max collapse into the same value.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err("width and height must be at least 1".to_string());
+        }
+        let bounds = [
+            self.real_min,
+            self.real_max,
+            self.imaginary_min,
+            self.imaginary_max,
+        ];
+        if bounds.iter().any(|bound| !bound.is_finite()) {
+            return Err("the viewport bounds must be finite numbers".to_string());
+        }
+        if self.real_min >= self.real_max || self.imaginary_min >= self.imaginary_max {
+            return Err("the viewport is empty: every min must be less than its max".to_string());
+        }
+        Ok(())
+    }
+
+    /// Converts a pixel coordinate into the point on the complex plane that it represents.
+    pub fn pixel_to_complex(&self, pixel_x: usize, pixel_y: usize) -> Complex<f64> {
+        // calculate pixel position as percentage of total width and height
+        let pixel_x_percent = pixel_x as f64 / self.width as f64;
+        let pixel_y_percent = pixel_y as f64 / self.height as f64;
+
+        /*
+        Here we calculate the pixel position on the complex plane, just as you would
+        on a regular 2D grid.
+          Example:
+            // on an x-axis where the min is 10 and the max is 30, halfway point is 20.
+            x_axis_max    = 30
+            x_axis_min    = 10
+            x_axis_length = 20   // 30 - 10
+
+            offset = 10          // always same as x_axis_min
+            pixel_position = 0.5 // a pixel exactly half way (50%) on the x axis
+
+            // to get the pixel position on the x axis:
+            cx = pixel_position * x_axis_length + offset
+            cx = 0.5 * 20 + 10 = 20
+        */
+        let x_axis_length = self.real_max - self.real_min;
+        let offset = self.real_min;
+        let cx = (pixel_x_percent * x_axis_length) + offset;
+
+        // do the same as above, but for the y_axis.
+        let y_axis_length = self.imaginary_max - self.imaginary_min;
+        let offset = self.imaginary_min;
+        let cy = (pixel_y_percent * y_axis_length) + offset;
+
+        Complex::new(cx, cy)
+    }
+}
+
+impl Default for View {
+    /// The whole set, sized for a full screen terminal.
+    fn default() -> View {
+        View::new(-2.0, 1.0, -1.0, 1.0, 230, 66)
+    }
+}
+
+/// Everything [`calculate_mandelbrot`](crate::calculate_mandelbrot) needs to know to compute a grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub view: View,
+    /// Points that have not escaped after this many iterations are considered part of the set.
+    pub max_iterations: usize,
+    /// When a point counts as escaped. Defaults to |z| > 2.
+    pub bailout: Bailout,
+    /// Skip the iterations for points in the main cardioid and the period-2 bulb, which are known
+    /// to be inside the set. Defaults to on.
+    ///
+    /// Only changes the speed, not the iteration counts. The rest of the
+    /// [`Escape`](crate::Escape) of a skipped point does differ: its orbit is never followed, so
+    /// `z` stays at the start, and the period is 1 or 2 from the shape it lies in, where the full
+    /// loop might not have found the cycle yet, close to the edge of the cardioid or bulb.
+    pub interior_shortcut: bool,
+    /// Stop iterating points whose orbit is caught in a cycle, since those never escape.
+    /// Defaults to on.
+    pub periodicity_check: bool,
+    /// How close z has to come back to an earlier value to count as a cycle.
+    pub periodicity_tolerance: f64,
+    /// Draw the Julia set of this constant instead: every pixel is the starting z, and c stays
+    /// the same for all of them. `None`, the default, draws the Mandelbrot set, where the pixel
+    /// is c and z starts at zero.
+    pub julia: Option<Complex<f64>>,
+    /// The equation that is iterated. Defaults to z² + c.
+    pub formula: Fractal,
+}
+
+impl Config {
+    pub fn new(view: View, max_iterations: usize) -> Config {
+        Config {
+            view,
+            max_iterations,
+            bailout: Bailout::default(),
+            interior_shortcut: true,
+            periodicity_check: true,
+            periodicity_tolerance: 1e-12,
+            julia: None,
+            formula: Fractal::Mandelbrot,
+        }
+    }
+
+    /// Checks the view, that at least one iteration is done per pixel, and that the bailout
+    /// radius, periodicity tolerance, Julia constant and Multibrot power make sense.
+    pub fn validate(&self) -> Result<(), String> {
+        self.view.validate()?;
+        if self.max_iterations == 0 {
+            return Err("iterations must be at least 1".to_string());
+        }
+        if !(self.bailout.radius.is_finite() && self.bailout.radius > 0.0) {
+            return Err("the bailout radius must be greater than 0".to_string());
+        }
+        if !(self.periodicity_tolerance.is_finite() && self.periodicity_tolerance >= 0.0) {
+            return Err("the periodicity tolerance must be 0 or more".to_string());
+        }
+        if let Some(power) = self.formula.power() {
+            Fractal::with_power(power)?;
+        }
+        if let Some(c) = self.julia {
+            if !(c.re.is_finite() && c.im.is_finite()) {
+                return Err("the Julia constant must be a finite number".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new(View::default(), 1000)
+    }
+}
@@ -0,0 +1,94 @@
+use rusty_mandelbrot::render::ansi::{render_ansi, to_ansi16, to_xterm256, ColorDepth, RESET};
+use rusty_mandelbrot::Palette;
+
+#[test]
+fn greys_map_to_the_grey_ramp() {
+    // the 24 greys go from 8 to 238 in steps of 10.
+    for step in 0..24u8 {
+        let level = 8 + step * 10;
+        assert_eq!(to_xterm256([level; 3]), 232 + step, "grey {}", level);
+        // nearby greys round to the same step.
+        assert_eq!(
+            to_xterm256([level + 2; 3]),
+            232 + step,
+            "grey {}",
+            level + 2
+        );
+    }
+    // black and white are corners of the cube, closer than the ends of the ramp.
+    assert_eq!(to_xterm256([0; 3]), 16);
+    assert_eq!(to_xterm256([255; 3]), 231);
+}
+
+#[test]
+fn cube_colors_map_to_their_index() {
+    for (rgb, index) in [
+        ([255, 0, 0], 196),
+        ([0, 255, 0], 46),
+        ([0, 0, 255], 21),
+        ([255, 255, 0], 226),
+        ([255, 0, 255], 201),
+        ([0, 255, 255], 51),
+        // 16 + 36 * 1 + 6 * 2 + 3, with the levels 0, 95, 135, 175, 215, 255.
+        ([95, 135, 175], 67),
+        ([100, 130, 180], 67),
+    ] {
+        assert_eq!(to_xterm256(rgb), index, "{:?}", rgb);
+    }
+}
+
+#[test]
+fn sixteen_colors_use_the_redmean_distance() {
+    assert_eq!(to_ansi16([0, 0, 0]), 0);
+    assert_eq!(to_ansi16([255, 255, 255]), 15);
+    assert_eq!(to_ansi16([200, 10, 10]), 1);
+    assert_eq!(to_ansi16([250, 250, 10]), 11);
+    // plain RGB distance picks the dark blue (4) for this one, the weighted distance the light
+    // blue (92, 92, 255) that it looks closer to.
+    assert_eq!(to_ansi16([0, 80, 208]), 12);
+    // and the grey (127, 127, 127) over black for a dark teal.
+    assert_eq!(to_ansi16([0, 64, 112]), 8);
+}
+
+#[test]
+fn escapes_for_each_depth() {
+    let rgb = [255, 0, 0];
+    assert_eq!(ColorDepth::TrueColor.foreground(rgb), "\x1b[38;2;255;0;0m");
+    assert_eq!(ColorDepth::TrueColor.background(rgb), "\x1b[48;2;255;0;0m");
+    assert_eq!(ColorDepth::Xterm256.foreground(rgb), "\x1b[38;5;196m");
+    assert_eq!(ColorDepth::Xterm256.background(rgb), "\x1b[48;5;196m");
+    // the bright colours are 90-97 and 100-107, the others 30-37 and 40-47.
+    assert_eq!(ColorDepth::Ansi16.foreground(rgb), "\x1b[91m");
+    assert_eq!(ColorDepth::Ansi16.background(rgb), "\x1b[101m");
+    assert_eq!(ColorDepth::Ansi16.foreground([205, 0, 0]), "\x1b[31m");
+    assert_eq!(ColorDepth::Ansi16.background([205, 0, 0]), "\x1b[41m");
+}
+
+#[test]
+fn render_ansi_draws_colored_cells() {
+    // a row of one colour, and a row that changes colour at every cell.
+    let points = vec![vec![100usize; 3], vec![50, 100, 50]];
+    let palette = Palette::Fire;
+    for (depth, prefix) in [
+        (ColorDepth::TrueColor, "\x1b[48;2;"),
+        (ColorDepth::Xterm256, "\x1b[48;5;"),
+        (ColorDepth::Ansi16, "\x1b["),
+    ] {
+        let mut out = Vec::new();
+        render_ansi(&points, 100, palette, depth, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        // the inside of the set, then a reset before the newline.
+        let inside = depth.background(palette.color_iterations(100usize, 100));
+        assert_eq!(lines[0], format!("{}   {}", inside, RESET));
+        let outside = depth.background(palette.color_iterations(50usize, 100));
+        assert_eq!(
+            lines[1],
+            format!("{} {} {} {}", outside, inside, outside, RESET)
+        );
+        assert!(inside.starts_with(prefix), "{:?}", inside);
+        assert!(text.ends_with("\x1b[0m\n"));
+    }
+}
@@ -0,0 +1,61 @@
+use num::complex::Complex;
+
+use rusty_mandelbrot::{Bailout, Norm};
+
+#[test]
+fn norms_measure_their_shapes() {
+    let corner = Complex::new(1.5, 1.5);
+    let right = Complex::new(2.5, 0.0);
+    let up = Complex::new(0.0, -2.5);
+    for (norm, escaped) in [
+        (Norm::Euclidean, [true, true, true]),
+        (Norm::Manhattan, [true, true, true]),
+        (Norm::Chebyshev, [false, true, true]),
+        (Norm::Real, [false, true, false]),
+        (Norm::Imaginary, [false, false, true]),
+    ] {
+        let bailout = Bailout::new(2.0, norm);
+        assert_eq!(
+            [corner, right, up].map(|z| bailout.escaped(z)),
+            escaped,
+            "{}",
+            norm.name()
+        );
+        // the radius itself is still inside.
+        assert!(!bailout.escaped(Complex::new(2.0, 0.0)));
+        assert!(!bailout.escaped(Complex::new(0.0, 0.0)));
+    }
+    assert!(!Bailout::new(2.0, Norm::Manhattan).escaped(Complex::new(1.0, -1.0)));
+    assert!(Bailout::new(2.0, Norm::Manhattan).escaped(Complex::new(1.0, -1.01)));
+}
+
+#[test]
+fn contains_set_is_exactly_the_disc_of_radius_2() {
+    // the orbits of the set stay in |z| <= 2, so that disc has to fit inside the bailout.
+    let circle: Vec<Complex<f64>> = (0..3600)
+        .map(|step| Complex::from_polar(2.0, step as f64 / 3600.0 * std::f64::consts::TAU))
+        .collect();
+    for norm in Norm::ALL {
+        let smallest = match norm {
+            Norm::Manhattan => 2.0 * std::f64::consts::SQRT_2,
+            _ => 2.0,
+        };
+        // a hair of slack for the rounding of the points on the circle.
+        let fits = Bailout::new(smallest + 1e-12, norm);
+        assert!(fits.contains_set(), "{}", norm.name());
+        assert!(circle.iter().all(|&z| !fits.escaped(z)), "{}", norm.name());
+
+        let tight = Bailout::new(smallest * 0.99, norm);
+        assert!(!tight.contains_set(), "{}", norm.name());
+        assert!(circle.iter().any(|&z| tight.escaped(z)), "{}", norm.name());
+    }
+    assert!(Bailout::default().contains_set());
+}
+
+#[test]
+fn names_round_trip() {
+    for norm in Norm::ALL {
+        assert_eq!(Norm::from_name(norm.name()), Some(norm));
+    }
+    assert_eq!(Norm::from_name("round"), None);
+}
@@ -0,0 +1,67 @@
+/*
+Checks that the Buddhabrot samples the same orbits however the work is spread over threads, and
+that it counts the orbits it should.
+*/
+
+use rusty_mandelbrot::buddhabrot::{self, Buddhabrot};
+use rusty_mandelbrot::{calculate_buddhabrot, Config, View};
+
+fn config() -> Config {
+    Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 48, 32), 200)
+}
+
+#[test]
+fn same_picture_on_any_number_of_threads() {
+    let buddhabrot = Buddhabrot {
+        samples: 50_000,
+        nebula: Some([200, 50, 10]),
+        ..Buddhabrot::default()
+    };
+    let single = calculate_buddhabrot(&config(), &buddhabrot, 1);
+    assert_eq!(calculate_buddhabrot(&config(), &buddhabrot, 3), single);
+
+    let reseeded = Buddhabrot {
+        seed: 2,
+        ..buddhabrot
+    };
+    assert_ne!(calculate_buddhabrot(&config(), &reseeded, 1), single);
+}
+
+#[test]
+fn anti_buddhabrot_lights_up_the_set() {
+    let anti = Buddhabrot {
+        samples: 50_000,
+        anti: true,
+        ..Buddhabrot::default()
+    };
+    let density = calculate_buddhabrot(&config(), &anti, 2);
+    // orbits inside the set never leave the disc |z| <= 2, so the corners stay dark, while the
+    // main cardioid, where most of them stay, is lit.
+    assert_eq!(density[0][0].channels, [0.0; 3]);
+    assert!(density[16][32].channels[0] > 0.0);
+    assert!(density.iter().flatten().all(|point| !point.nebula));
+}
+
+#[test]
+fn tone_map_stays_between_0_and_1() {
+    let counts: Vec<u64> = (0..10_000).map(|count| count % 997).collect();
+    let brightness = buddhabrot::tone_map(&counts);
+    assert_eq!(brightness[0], 0.0);
+    assert!(brightness.iter().all(|value| (0.0..=1.0).contains(value)));
+    assert!(brightness
+        .windows(2)
+        .take(996)
+        .all(|pair| pair[0] < pair[1]));
+    assert_eq!(buddhabrot::tone_map(&[0, 0]), [0.0, 0.0]);
+}
+
+#[test]
+fn tone_map_takes_counts_past_32_bits() {
+    let counts: Vec<u64> = (0..10_000).map(|count| count << 32).collect();
+    let brightness = buddhabrot::tone_map(&counts);
+    assert_eq!(brightness[0], 0.0);
+    assert!(brightness
+        .windows(2)
+        .take(9000)
+        .all(|pair| pair[0] < pair[1]));
+}
@@ -0,0 +1,55 @@
+/*
+Checks that formulas typed in iterate the same as the built-in ones they spell out.
+*/
+
+use std::sync::Arc;
+
+use rusty_mandelbrot::expression::Expression;
+use rusty_mandelbrot::{calculate_mandelbrot, Config, Fractal, View};
+
+fn grid(formula: Fractal) -> Vec<Vec<usize>> {
+    let mut config = Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 60, 40), 200);
+    config.formula = formula;
+    calculate_mandelbrot(&config)
+}
+
+fn custom(source: &str) -> Fractal {
+    Fractal::Custom(Arc::new(Expression::parse(source).unwrap()))
+}
+
+#[test]
+fn expressions_match_built_in_formulas() {
+    let mandelbrot = grid(Fractal::Mandelbrot);
+    assert_eq!(grid(custom("z^2 + c")), mandelbrot);
+    assert_eq!(grid(custom("z*z + c")), mandelbrot);
+    assert_eq!(grid(custom("pow(z, 2) + c")), mandelbrot);
+
+    assert_eq!(grid(custom("z^3 + c")), grid(Fractal::Multibrot(3.0)));
+    assert_eq!(grid(custom("conj(z)^2 + c")), grid(Fractal::Tricorn));
+}
+
+#[test]
+fn invalid_expressions() {
+    for source in [
+        "",
+        "z +",
+        "(z",
+        "z ^^ 2",
+        "sin(z, c)",
+        "foo(z)",
+        "z + q",
+        "z; z0 = ",
+    ] {
+        assert!(Expression::parse(source).is_err(), "{}", source);
+    }
+    let nested = format!("{}z{}", "(".repeat(1000), ")".repeat(1000));
+    assert!(Expression::parse(&nested).is_err());
+
+    // long chains are turned away while parsing too, before they make a tree too deep to walk.
+    for operator in ["+", "-", "*", "/"] {
+        let chain = format!("z{}", format!(" {} c", operator).repeat(100_000));
+        assert!(Expression::parse(&chain).is_err(), "{}", operator);
+    }
+    let chain = format!("z{}", " + c*c*c".repeat(40));
+    assert!(Expression::parse(&chain).is_ok());
+}
@@ -0,0 +1,126 @@
+use num::complex::Complex;
+
+use rusty_mandelbrot::formula::{
+    Buffalo, BurningShip, Celtic, Mandelbrot, Multibrot, Perpendicular, Tricorn,
+};
+use rusty_mandelbrot::{Bailout, Formula, Fractal};
+
+// points in every quadrant, on the axes, and off the unit circle both ways.
+fn points() -> Vec<Complex<f64>> {
+    let mut points = Vec::new();
+    for re in [-1.7, -0.3, 0.0, 0.45, 1.2] {
+        for im in [-0.9, -0.05, 0.0, 0.6, 1.4] {
+            points.push(Complex::new(re, im));
+        }
+    }
+    points
+}
+
+fn assert_close(actual: Complex<f64>, expected: Complex<f64>, what: &str) {
+    assert!(
+        (actual - expected).norm() <= 1e-12 * (1.0 + expected.norm()),
+        "{}: {} instead of {}",
+        what,
+        actual,
+        expected
+    );
+}
+
+// each formula as written in its documentation, with complex numbers instead of its parts.
+fn closed_form(fractal: &Fractal, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+    let fold = |z: Complex<f64>| Complex::new(z.re.abs(), z.im.abs());
+    match fractal {
+        Fractal::Mandelbrot => z * z + c,
+        Fractal::Multibrot(power) => (z.ln() * power).exp() + c,
+        Fractal::BurningShip => fold(z) * fold(z) + c,
+        Fractal::Tricorn => z.conj() * z.conj() + c,
+        Fractal::Celtic => {
+            let square = z * z;
+            Complex::new(square.re.abs(), square.im) + c
+        }
+        Fractal::Buffalo => {
+            let square = z * z;
+            Complex::new(square.re.abs(), -square.im.abs()) + c
+        }
+        Fractal::Perpendicular => {
+            let folded = Complex::new(z.re.abs(), -z.im);
+            folded * folded + c
+        }
+        Fractal::Custom(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn steps_match_their_closed_forms() {
+    let c = Complex::new(-0.4, 0.25);
+    let mut fractals = Fractal::ALL.to_vec();
+    fractals.push(Fractal::Multibrot(2.5));
+    fractals.push(Fractal::Multibrot(7.0));
+    for fractal in &fractals {
+        for z in points() {
+            // the polar form of a Multibrot power has nothing to say at zero.
+            if z == Complex::new(0.0, 0.0) && matches!(fractal, Fractal::Multibrot(_)) {
+                continue;
+            }
+            let expected = closed_form(fractal, z, c);
+            assert_close(fractal.step(z, c), expected, fractal.name());
+        }
+    }
+}
+
+#[test]
+fn fractal_steps_as_the_formula_it_picks() {
+    let c = Complex::new(0.3, -0.7);
+    let formulas: [(Fractal, &dyn Formula); 7] = [
+        (Fractal::Mandelbrot, &Mandelbrot),
+        (Fractal::Multibrot(3.0), &Multibrot { power: 3.0 }),
+        (Fractal::BurningShip, &BurningShip),
+        (Fractal::Tricorn, &Tricorn),
+        (Fractal::Celtic, &Celtic),
+        (Fractal::Buffalo, &Buffalo),
+        (Fractal::Perpendicular, &Perpendicular),
+    ];
+    for (fractal, formula) in formulas {
+        for z in points() {
+            assert_eq!(fractal.step(z, c), formula.step(z, c), "{}", fractal.name());
+        }
+        assert_eq!(fractal.degree(), formula.degree());
+        assert_eq!(fractal.default_view(), formula.default_view());
+        assert_eq!(fractal.default_bailout(), formula.default_bailout());
+    }
+}
+
+#[test]
+fn typed_in_formulas_step_as_the_built_in_ones() {
+    let c = Complex::new(-0.4, 0.25);
+    for (text, fractal) in [
+        ("z^2 + c", Fractal::Mandelbrot),
+        ("z*z*z + c", Fractal::Multibrot(3.0)),
+        ("conj(z)^2 + c", Fractal::Tricorn),
+    ] {
+        let custom = Fractal::parse(text).unwrap();
+        assert!(matches!(custom, Fractal::Custom(_)));
+        for z in points() {
+            assert_close(custom.step(z, c), fractal.step(z, c), text);
+        }
+    }
+}
+
+#[test]
+fn only_z_squared_plus_c_has_the_mandelbrot_interior() {
+    for fractal in Fractal::ALL {
+        assert_eq!(
+            fractal.has_mandelbrot_interior(),
+            fractal == Fractal::Mandelbrot,
+            "{}",
+            fractal.name()
+        );
+    }
+}
+
+#[test]
+fn typed_in_formulas_have_the_plain_bailout() {
+    let custom = Fractal::parse("z^5 + c").unwrap();
+    assert_eq!(custom.default_bailout(), Bailout::default());
+    assert!(Fractal::Multibrot(1.5).default_bailout().radius > 2.0);
+}
@@ -0,0 +1,68 @@
+/*
+Compares the byte streams of the terminal graphics encoders against files in tests/golden.
+
+The grids are small, so the files stay readable in a hex dump. After an intended change to an
+encoder, regenerate the files with:
+
+  UPDATE_GOLDEN=1 cargo test --test golden
+
+and check the new images in a terminal that supports them (cat tests/golden/<name>).
+*/
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rusty_mandelbrot::render::{kitty, sixel};
+use rusty_mandelbrot::{calculate_mandelbrot, calculate_mandelbrot_smooth, Config, Palette, View};
+
+fn check_golden(name: &str, actual: &[u8]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read(&path)
+        .unwrap_or_else(|error| panic!("could not read {}: {}", path.display(), error));
+    assert!(
+        expected == actual,
+        "output differs from {} (run with UPDATE_GOLDEN=1 to regenerate it)",
+        path.display()
+    );
+}
+
+// the whole set, small enough for a handful of colours.
+fn small_config() -> Config {
+    Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 30, 20), 40)
+}
+
+#[test]
+fn sixel_few_colors() {
+    let points = calculate_mandelbrot(&small_config());
+    let mut out = Vec::new();
+    sixel::write_sixel(&points, 40, Palette::Fire, &mut out).unwrap();
+    check_golden("few_colors.six", &out);
+}
+
+#[test]
+fn sixel_reduced_palette() {
+    // smooth colouring gives far more than 256 colours, so the palette has to be reduced.
+    let config = Config::new(View::new(-0.8, -0.7, 0.05, 0.15, 96, 72), 300);
+    let points = calculate_mandelbrot_smooth(&config);
+    let mut out = Vec::new();
+    sixel::write_sixel(&points, 300, Palette::Rainbow, &mut out).unwrap();
+    check_golden("reduced_palette.six", &out);
+}
+
+#[test]
+fn kitty_chunked() {
+    // 40x30 RGBA pixels are 6400 bytes of base64, which needs a second chunk.
+    let config = Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 40, 30), 40);
+    let points = calculate_mandelbrot(&config);
+    let mut out = Vec::new();
+    kitty::write_kitty(&points, 40, Palette::Ocean, &mut out).unwrap();
+    check_golden("chunked.kitty", &out);
+}
@@ -0,0 +1,99 @@
+use num::complex::Complex;
+
+use rusty_mandelbrot::{calculate_mandelbrot, num_of_mandelbrot_iters_before_escape, Config, View};
+
+const MAX_ITERATIONS: usize = 500;
+
+// the square around zero that every Julia set of z² + c with |c| <= 2 fits in.
+fn julia_config(c: Complex<f64>) -> Config {
+    let mut config = Config::new(View::new(-2.0, 2.0, -2.0, 2.0, 80, 80), MAX_ITERATIONS);
+    config.julia = Some(c);
+    config
+}
+
+// the share of the grid that never escaped.
+fn inside(c: Complex<f64>) -> f64 {
+    let points = calculate_mandelbrot(&julia_config(c));
+    let inside = points
+        .iter()
+        .flatten()
+        .filter(|&&iterations| iterations == MAX_ITERATIONS)
+        .count();
+    inside as f64 / (80 * 80) as f64
+}
+
+#[test]
+fn julia_set_of_zero_is_the_unit_disc() {
+    let config = julia_config(Complex::new(0.0, 0.0));
+    let points = calculate_mandelbrot(&config);
+    for (pixel_y, row) in points.iter().enumerate() {
+        for (pixel_x, &iterations) in row.iter().enumerate() {
+            let z = config.view.pixel_to_complex(pixel_x, pixel_y);
+            if z.norm() < 0.95 {
+                assert_eq!(iterations, MAX_ITERATIONS, "{}", z);
+            } else if z.norm() > 1.05 {
+                assert!(iterations < MAX_ITERATIONS, "{}", z);
+            }
+        }
+    }
+}
+
+#[test]
+fn connected_julia_sets_keep_their_middle() {
+    // c in the Mandelbrot set: the basilica, Douady's rabbit and the dendrite at c = i, whose
+    // Julia sets are connected, so the orbit of zero never escapes.
+    let basilica = Complex::new(-1.0, 0.0);
+    let rabbit = Complex::new(-0.122_561_166_876_654, 0.744_861_766_619_744);
+    let dendrite = Complex::new(0.0, 1.0);
+    let config = julia_config(basilica);
+    for c in [basilica, rabbit, dendrite] {
+        let zero = Complex::new(0.0, 0.0);
+        assert_eq!(
+            num_of_mandelbrot_iters_before_escape(c, zero, &config),
+            MAX_ITERATIONS
+        );
+    }
+    // the basilica and the rabbit have insides to fill, the dendrite is all edges.
+    assert!(inside(basilica) > 0.05);
+    assert!(inside(rabbit) > 0.05);
+    assert!(inside(dendrite) < 0.01);
+}
+
+#[test]
+fn disconnected_julia_sets_are_dust() {
+    // c outside the Mandelbrot set: the Julia set falls apart into dust, with nothing inside.
+    // Only the odd grid point that lands right on it stays, like the fixed point 0.5 + 0.5i of
+    // z² + 0.5.
+    for c in [
+        Complex::new(0.5, 0.0),
+        Complex::new(-2.1, 0.0),
+        Complex::new(0.3, 0.6),
+        Complex::new(-0.8, 0.2),
+    ] {
+        assert!(inside(c) < 0.001, "{}", c);
+    }
+}
+
+#[test]
+fn julia_origin_is_the_mandelbrot_point() {
+    // z and c swap roles, so the middle of the Julia set of c is the point c of the Mandelbrot
+    // set, which runs exactly the same orbit.
+    let mandelbrot = Config::new(View::default(), MAX_ITERATIONS);
+    for c in [
+        Complex::new(-0.75, 0.1),
+        Complex::new(0.3, 0.6),
+        Complex::new(-1.3, 0.0),
+        Complex::new(0.26, 0.0),
+    ] {
+        let config = julia_config(c);
+        // pixel 40 of 80 is exactly zero.
+        assert_eq!(config.view.pixel_to_complex(40, 40), Complex::new(0.0, 0.0));
+        let points = calculate_mandelbrot(&config);
+        assert_eq!(
+            points[40][40],
+            num_of_mandelbrot_iters_before_escape(c, Complex::new(0.0, 0.0), &mandelbrot),
+            "{}",
+            c
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::fs;
+
+use num::complex::Complex;
+use rusty_mandelbrot::bookmarks::Bookmarks;
+use rusty_mandelbrot::location::{History, Location};
+
+fn at(re: f64) -> Location {
+    Location {
+        center: Complex::new(re, 0.0),
+        ..Location::default()
+    }
+}
+
+#[test]
+fn history_steps_back_and_forward() {
+    let mut history = History::default();
+    assert_eq!(history.undo(at(0.0)), None);
+    assert_eq!(history.redo(at(0.0)), None);
+
+    history.push(at(0.0));
+    history.push(at(1.0));
+    // now at 2.
+    assert_eq!(history.undo(at(2.0)), Some(at(1.0)));
+    assert_eq!(history.undo(at(1.0)), Some(at(0.0)));
+    assert_eq!(history.undo(at(0.0)), None);
+    assert_eq!(history.redo(at(0.0)), Some(at(1.0)));
+    assert_eq!(history.redo(at(1.0)), Some(at(2.0)));
+    assert_eq!(history.redo(at(2.0)), None);
+
+    // moving somewhere new after an undo forgets what was undone.
+    assert_eq!(history.undo(at(2.0)), Some(at(1.0)));
+    history.push(at(1.0));
+    assert_eq!(history.redo(at(3.0)), None);
+    assert_eq!(history.undo(at(3.0)), Some(at(1.0)));
+}
+
+#[test]
+fn history_forgets_the_oldest_steps() {
+    let mut history = History::default();
+    let steps = History::LIMIT + 10;
+    for step in 0..steps {
+        history.push(at(step as f64));
+    }
+    let mut current = at(steps as f64);
+    let mut undone = 0;
+    while let Some(previous) = history.undo(current.clone()) {
+        current = previous;
+        undone += 1;
+    }
+    assert_eq!(undone, History::LIMIT);
+    assert_eq!(current, at(10.0));
+}
+
+#[test]
+fn bookmarks_are_saved_and_loaded() {
+    let path = std::env::temp_dir().join(format!("bookmarks-test-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    // a file that is not there yet has no bookmarks.
+    assert_eq!(Bookmarks::load(&path), Ok(Bookmarks::default()));
+
+    let seahorse: Location = "center=-0.75,0.1 zoom=20 iterations=2000 palette=ocean"
+        .parse()
+        .unwrap();
+    let mut bookmarks = Bookmarks::default();
+    bookmarks.insert("seahorse", seahorse.clone()).unwrap();
+    bookmarks.insert("home", Location::default()).unwrap();
+    bookmarks.save(&path).unwrap();
+    let loaded = Bookmarks::load(&path).unwrap();
+    assert_eq!(loaded, bookmarks);
+    assert_eq!(loaded.get("seahorse"), Some(&seahorse));
+
+    // saving under the same name replaces the bookmark, and keeps its place.
+    let mut loaded = loaded;
+    loaded.insert("seahorse", at(0.25)).unwrap();
+    loaded.save(&path).unwrap();
+    let reloaded = Bookmarks::load(&path).unwrap();
+    let names: Vec<&str> = reloaded.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["seahorse", "home"]);
+    assert_eq!(reloaded.get("seahorse"), Some(&at(0.25)));
+
+    assert!(reloaded.clone().insert("two words", at(0.0)).is_err());
+    assert!(reloaded.clone().insert("#comment", at(0.0)).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_locations_are_rejected() {
+    for (text, message) in [
+        ("zoom=2", "a location needs a center"),
+        ("", "a location needs a center"),
+        (
+            "center=0,0 power=3",
+            "power only goes with formula=multibrot",
+        ),
+        (
+            "center=0,0 formula=tricorn power=3",
+            "power only goes with formula=multibrot",
+        ),
+        ("center=0,0 zoom=0", "invalid zoom '0'"),
+        ("center=0,0 zoom=-2", "invalid zoom '-2'"),
+        ("center=0,0 zoom=inf", "invalid zoom 'inf'"),
+        ("center=0,0 iterations=0", "invalid iterations '0'"),
+        ("center=0,0 depth=3", "unknown key 'depth'"),
+        ("center=0", "invalid center '0'"),
+        ("center=0,0 zoom", "expected key=value, found 'zoom'"),
+    ] {
+        assert_eq!(
+            text.parse::<Location>(),
+            Err(message.to_string()),
+            "{}",
+            text
+        );
+    }
+    assert!("center=0,0 formula=multibrot power=3"
+        .parse::<Location>()
+        .is_ok());
+}
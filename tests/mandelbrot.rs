@@ -0,0 +1,247 @@
+use num::complex::Complex;
+
+use rusty_mandelbrot::mandelbrot::{smooth_iteration_count, smooth_iteration_count_with_degree};
+use rusty_mandelbrot::parallel;
+use rusty_mandelbrot::{
+    calculate_escapes, calculate_mandelbrot, calculate_mandelbrot_parallel,
+    calculate_mandelbrot_smooth, calculate_mandelbrot_smooth_parallel, escape_time,
+    smooth_iters_before_escape, Config, Escape, View,
+};
+
+fn config(width: usize, height: usize) -> Config {
+    Config::new(View::new(-2.0, 0.5, -1.1, 1.1, width, height), 200)
+}
+
+#[test]
+fn parallel_grids_match_serial() {
+    // height 0 and 1 are the edge cases of splitting the rows; 7 rows over 2 and 3 threads do
+    // not divide evenly; 16 threads is more threads than rows.
+    for height in [0, 1, 2, 7] {
+        let config = config(13, height);
+        let serial = calculate_mandelbrot(&config);
+        let smooth = calculate_mandelbrot_smooth(&config);
+        assert_eq!(serial.len(), height);
+        for threads in [0, 1, 2, 3, 16] {
+            assert_eq!(
+                calculate_mandelbrot_parallel(&config, threads),
+                serial,
+                "{} rows on {} threads",
+                height,
+                threads
+            );
+            let parallel_smooth = calculate_mandelbrot_smooth_parallel(&config, threads);
+            // compared bit for bit: the same row has to be computed the same way.
+            let bits = |grid: &[Vec<f64>]| -> Vec<Vec<u64>> {
+                grid.iter()
+                    .map(|row| row.iter().map(|value| value.to_bits()).collect())
+                    .collect()
+            };
+            assert_eq!(bits(&parallel_smooth), bits(&smooth));
+            let escapes = calculate_escapes(&config, threads);
+            let iterations: Vec<Vec<usize>> = escapes
+                .iter()
+                .map(|row| row.iter().map(|escape| escape.iterations).collect())
+                .collect();
+            assert_eq!(iterations, serial);
+        }
+    }
+}
+
+#[test]
+fn rows_come_back_in_order() {
+    for threads in [1, 2, 3, 50] {
+        let rows = parallel::map_rows(20, threads, |pixel_y| vec![pixel_y; 3]);
+        let expected: Vec<Vec<usize>> = (0..20).map(|pixel_y| vec![pixel_y; 3]).collect();
+        assert_eq!(rows, expected);
+    }
+    assert!(parallel::map_rows(0, 4, |pixel_y| vec![pixel_y]).is_empty());
+}
+
+#[test]
+fn interior_shortcut_keeps_the_iteration_counts() {
+    // the cardioid and the bulb, with their edges, and the set around them.
+    let mut with = Config::new(View::new(-1.3, 0.4, -0.8, 0.8, 90, 70), 500);
+    for periodicity_check in [true, false] {
+        with.periodicity_check = periodicity_check;
+        let mut without = with.clone();
+        without.interior_shortcut = false;
+        assert_eq!(calculate_mandelbrot(&with), calculate_mandelbrot(&without));
+    }
+}
+
+#[test]
+fn interior_shortcut_reports_the_shape_it_found() {
+    let mut config = Config::new(View::new(-2.0, 0.5, -1.1, 1.1, 10, 10), 1000);
+    let zero = Complex::new(0.0, 0.0);
+    for (c, period) in [(Complex::new(-0.1, 0.1), 1), (Complex::new(-1.0, 0.05), 2)] {
+        let skipped = escape_time(c, zero, &config);
+        assert_eq!(
+            skipped,
+            Escape {
+                iterations: 1000,
+                escaped: false,
+                z: zero,
+                period: Some(period),
+            }
+        );
+
+        // the full loop finds the same cycle far from the edges, with z on it.
+        config.interior_shortcut = false;
+        let followed = escape_time(c, zero, &config);
+        config.interior_shortcut = true;
+        assert_eq!(followed.iterations, 1000);
+        assert!(!followed.escaped);
+        assert_eq!(followed.period, Some(period));
+        assert_ne!(followed.z, zero);
+    }
+}
+
+#[test]
+fn periodicity_check_stops_at_the_period() {
+    let mut config = Config::new(View::default(), 1 << 40);
+    // the centers of bulbs outside the cardioid and the period-2 bulb, where only the
+    // periodicity check can stop the orbit. Without it, 2^40 iterations would not finish.
+    for (c, period) in [
+        (
+            Complex::new(-0.122_561_166_876_654, 0.744_861_766_619_744),
+            3,
+        ),
+        (Complex::new(-1.754_877_666_246_693, 0.0), 3),
+        (Complex::new(-1.310_702_641_336_832_8, 0.0), 4),
+        (
+            Complex::new(0.282_271_390_766_239_3, 0.530_060_617_578_525_4),
+            4,
+        ),
+        (
+            Complex::new(-0.504_340_175_446_244_3, 0.562_765_761_452_981_9),
+            5,
+        ),
+    ] {
+        let escape = escape_time(c, Complex::new(0.0, 0.0), &config);
+        assert!(!escape.escaped);
+        assert_eq!(escape.iterations, 1 << 40);
+        assert_eq!(escape.period, Some(period), "{}", c);
+    }
+    // a wide tolerance finds the cycle too, even inside the cardioid without the shortcut.
+    config.interior_shortcut = false;
+    config.periodicity_tolerance = 1e-6;
+    let escape = escape_time(Complex::new(-0.2, 0.1), Complex::new(0.0, 0.0), &config);
+    assert_eq!(escape.period, Some(1));
+}
+
+#[test]
+fn periodicity_check_keeps_the_escape_counts() {
+    // the edges of the cardioid, the period-2 and period-3 bulbs, where escaping orbits linger
+    // near cycles for a long time before they leave.
+    for view in [
+        View::new(-0.8, -0.7, 0.05, 0.15, 40, 40),
+        View::new(0.24, 0.26, -0.01, 0.01, 40, 40),
+        View::new(-0.2, -0.05, 0.6, 0.75, 40, 40),
+    ] {
+        let with = Config::new(view, 5000);
+        let mut without = with.clone();
+        without.periodicity_check = false;
+        let checked = calculate_escapes(&with, 1);
+        let full = calculate_escapes(&without, 1);
+        for (checked, full) in checked.iter().flatten().zip(full.iter().flatten()) {
+            assert_eq!(checked.iterations, full.iterations);
+            assert_eq!(checked.escaped, full.escaped);
+            if checked.escaped {
+                assert_eq!(checked.z, full.z);
+            }
+        }
+        // some of both, or the view shows nothing.
+        assert!(checked.iter().flatten().any(|escape| escape.escaped));
+        assert!(checked.iter().flatten().any(|escape| !escape.escaped));
+    }
+}
+
+#[test]
+fn escaping_on_the_last_step_counts_as_inside() {
+    let c = Complex::new(0.3, 0.6);
+    let zero = Complex::new(0.0, 0.0);
+    let mut config = Config::new(View::default(), 1000);
+    let steps = escape_time(c, zero, &config);
+    assert!(steps.escaped);
+    let steps = steps.iterations;
+
+    // with one more iteration allowed, the escape is seen, after the same number of steps.
+    config.max_iterations = steps + 1;
+    let escape = escape_time(c, zero, &config);
+    assert!(escape.escaped);
+    assert_eq!(escape.iterations, steps);
+
+    // with exactly that many, z is never tested after the last step, and the point gets the
+    // count of the points in the set, as it would if it was tested and escaped there.
+    config.max_iterations = steps;
+    let escape = escape_time(c, zero, &config);
+    assert!(!escape.escaped);
+    assert_eq!(escape.iterations, steps);
+    assert!(config.bailout.escaped(escape.z));
+}
+
+#[test]
+fn smooth_count_exact_values() {
+    // mu = n + 1 - log2(log2|z|): |z| = 2^16 takes off log2(16) = 4, and |z| = 2^256 takes off 8.
+    assert_eq!(smooth_iteration_count(10, Complex::new(65536.0, 0.0)), 7.0);
+    assert_eq!(smooth_iteration_count(10, Complex::new(0.0, -65536.0)), 7.0);
+    assert_eq!(
+        smooth_iteration_count(20, Complex::new(2f64.powi(256), 0.0)),
+        13.0
+    );
+    // for z³ + c the outer logarithm is to base 3: |z| = 2^9 takes off 2.
+    let cubic = smooth_iteration_count_with_degree(10, Complex::new(512.0, 0.0), 3.0);
+    assert!((cubic - 9.0).abs() < 1e-12, "{}", cubic);
+    // never below zero, even for a z far out after no iterations at all.
+    assert_eq!(smooth_iteration_count(0, Complex::new(65536.0, 0.0)), 0.0);
+}
+
+#[test]
+fn smooth_count_is_continuous_across_bands() {
+    // one more step raises a large z to the power of the degree, and the count should not move.
+    for degree in [2.0, 3.0, 5.5] {
+        for z in [Complex::new(300.0, 0.0), Complex::new(-1e3, 2e3)] {
+            let next = z.powf(degree);
+            let before = smooth_iteration_count_with_degree(10, z, degree);
+            let after = smooth_iteration_count_with_degree(11, next, degree);
+            assert!((before - after).abs() < 1e-9, "{} at {}", degree, z);
+        }
+    }
+
+    // along a line out of the set, the count changes a little from point to point, also where
+    // the plain count jumps from one band to the next.
+    let config = Config::new(View::new(0.26, 0.6, 0.0, 0.0, 3400, 1), 1000);
+    let smooth = &calculate_mandelbrot_smooth(&config)[0];
+    let plain = &calculate_mandelbrot(&config)[0];
+    let mut jumps = 0;
+    for index in 1..smooth.len() {
+        if plain[index] != plain[index - 1] {
+            jumps += 1;
+        }
+        let change = (smooth[index] - smooth[index - 1]).abs();
+        assert!(change < 0.2, "a step of {} at {}", change, index);
+    }
+    assert!(jumps > 10);
+}
+
+#[test]
+fn smooth_count_of_points_in_the_set_is_max_iterations() {
+    let mut config = Config::new(View::new(-2.0, 0.5, -1.1, 1.1, 50, 40), 300);
+    for interior_shortcut in [true, false] {
+        config.interior_shortcut = interior_shortcut;
+        let smooth = calculate_mandelbrot_smooth(&config);
+        let plain = calculate_mandelbrot(&config);
+        for (&smooth, &plain) in smooth.iter().flatten().zip(plain.iter().flatten()) {
+            if plain == 300 {
+                assert_eq!(smooth, 300.0);
+            } else {
+                assert!((0.0..300.0).contains(&smooth));
+            }
+        }
+    }
+    let c = Complex::new(-0.122_561_166_876_654, 0.744_861_766_619_744);
+    assert_eq!(
+        smooth_iters_before_escape(c, Complex::new(0.0, 0.0), &config),
+        300.0
+    );
+}
@@ -0,0 +1,100 @@
+/*
+Checks the exact bytes of the Netpbm writers: the header text, the sample width and byte order,
+and the order of the rows.
+*/
+
+use rusty_mandelbrot::render::netpbm::{write_pfm, write_pgm, write_ppm};
+use rusty_mandelbrot::Palette;
+
+// three rows of two, with the inside of the set (max_iterations) in the bottom right.
+fn grid(max_iterations: usize) -> Vec<Vec<usize>> {
+    vec![vec![0, 1], vec![7, 200], vec![255, max_iterations]]
+}
+
+// splits a file after its header, which is `lines` lines of text.
+fn split_header(bytes: &[u8], lines: usize) -> (&str, &[u8]) {
+    let mut end = 0;
+    for _ in 0..lines {
+        end += bytes[end..].iter().position(|&byte| byte == b'\n').unwrap() + 1;
+    }
+    (std::str::from_utf8(&bytes[..end]).unwrap(), &bytes[end..])
+}
+
+#[test]
+fn ppm_is_rgb_in_row_order() {
+    let points = grid(255);
+    let mut out = Vec::new();
+    write_ppm(&points, 255, Palette::Fire, &mut out).unwrap();
+    let (header, pixels) = split_header(&out, 3);
+    assert_eq!(header, "P6\n2 3\n255\n");
+
+    let expected: Vec<u8> = points
+        .iter()
+        .flatten()
+        .flat_map(|&count| Palette::Fire.color_iterations(count, 255))
+        .collect();
+    assert_eq!(pixels, expected);
+    // the inside of the set is black.
+    assert_eq!(pixels[15..], [0, 0, 0]);
+}
+
+#[test]
+fn pgm_is_8_bit_up_to_255() {
+    let mut out = Vec::new();
+    write_pgm(&grid(255), 255, &mut out).unwrap();
+    let (header, pixels) = split_header(&out, 3);
+    assert_eq!(header, "P5\n2 3\n255\n");
+    assert_eq!(pixels, [0, 1, 7, 200, 255, 255]);
+
+    // smooth counts are rounded.
+    let mut out = Vec::new();
+    write_pgm(&[vec![2.4, 2.6, 100.0]], 100, &mut out).unwrap();
+    assert_eq!(out, b"P5\n3 1\n100\n\x02\x03\x64");
+}
+
+#[test]
+fn pgm_is_16_bit_big_endian_above_255() {
+    let mut out = Vec::new();
+    write_pgm(&grid(1000), 1000, &mut out).unwrap();
+    let (header, pixels) = split_header(&out, 3);
+    assert_eq!(header, "P5\n2 3\n1000\n");
+    assert_eq!(pixels, [0, 0, 0, 1, 0, 7, 0, 200, 0, 255, 0x03, 0xE8]);
+}
+
+#[test]
+fn pgm_clamps_at_65535() {
+    let mut out = Vec::new();
+    write_pgm(&[vec![65535, 65536, 70_000, 100_000]], 100_000, &mut out).unwrap();
+    let (header, pixels) = split_header(&out, 3);
+    assert_eq!(header, "P5\n4 1\n65535\n");
+    assert_eq!(pixels, [0xFF; 8]);
+}
+
+#[test]
+fn pfm_is_little_endian_from_the_bottom_up() {
+    let values = vec![vec![1.5, -2.0], vec![0.25, 1000.0]];
+    let mut out = Vec::new();
+    write_pfm(&values, &mut out).unwrap();
+    let (header, pixels) = split_header(&out, 3);
+    // a negative scale says the floats are little endian.
+    assert_eq!(header, "Pf\n2 2\n-1.0\n");
+
+    let mut expected = Vec::new();
+    // the first row on disk is the last row of the grid.
+    for value in [0.25f32, 1000.0, 1.5, -2.0] {
+        expected.extend_from_slice(&value.to_le_bytes());
+    }
+    assert_eq!(pixels, expected);
+    assert_eq!(&pixels[..4], &[0x00, 0x00, 0x80, 0x3E]);
+}
+
+#[test]
+fn empty_grids_have_a_header_only() {
+    let empty: Vec<Vec<usize>> = Vec::new();
+    let mut out = Vec::new();
+    write_pgm(&empty, 10, &mut out).unwrap();
+    assert_eq!(out, b"P5\n0 0\n10\n");
+    let mut out = Vec::new();
+    write_pfm(&empty, &mut out).unwrap();
+    assert_eq!(out, b"Pf\n0 0\n-1.0\n");
+}
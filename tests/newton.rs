@@ -0,0 +1,57 @@
+/*
+Checks that Newton's method finds the roots it should, and sorts the plane into their basins.
+*/
+
+use num::complex::Complex;
+use rusty_mandelbrot::newton::{self, Newton};
+use rusty_mandelbrot::{calculate_newton, Config, View};
+
+#[test]
+fn roots_from_coefficients() {
+    // z^4 - 1 = (z - 1)(z + 1)(z - i)(z + i)
+    let numbers = newton::parse_numbers("1 0 0 0 -1").unwrap();
+    let newton = Newton::from_coefficients(&numbers).unwrap();
+    for expected in newton::parse_numbers("1 -1 0,1 0,-1").unwrap() {
+        let found = newton
+            .roots()
+            .iter()
+            .any(|&root| (root - expected).norm() < 1e-12);
+        assert!(found, "{} missing from {:?}", expected, newton.roots());
+    }
+}
+
+#[test]
+fn points_near_a_root_converge_to_it() {
+    let newton = Newton::default();
+    // 0.05 apart, with a pixel on every multiple of it.
+    let config = Config::new(View::new(-2.25, 2.25, -1.5, 1.5, 90, 60), 100);
+    let basins = calculate_newton(&config, &newton, 2);
+    for (index, root) in newton.roots().iter().enumerate() {
+        let pixel_x = ((root.re + 2.25) / 0.05).round() as usize;
+        let pixel_y = ((root.im + 1.5) / 0.05).round() as usize;
+        let point = basins[pixel_y][pixel_x];
+        assert!(point.converged);
+        assert_eq!(point.root, Some(index));
+    }
+    // the basins are symmetric around the real axis, with the two complex roots swapped.
+    let mirror = |root: Option<usize>| root.map(|root| [0, 2, 1][root]);
+    for pixel_y in 1..60 {
+        for (point, mirrored) in basins[pixel_y].iter().zip(&basins[60 - pixel_y]) {
+            assert_eq!(point.iterations, mirrored.iterations);
+            assert_eq!(point.root, mirror(mirrored.root));
+        }
+    }
+}
+
+#[test]
+fn numbers_round_trip() {
+    let numbers = [
+        Complex::new(1.0, 0.0),
+        Complex::new(-0.5, 3f64.sqrt() / 2.0),
+        Complex::new(0.1, -1e-300),
+    ];
+    let text = newton::format_numbers(&numbers);
+    assert_eq!(newton::parse_numbers(&text).unwrap(), numbers);
+    assert!(newton::parse_numbers("1,2,3").is_err());
+    assert!(newton::parse_numbers("nan").is_err());
+}
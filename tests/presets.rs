@@ -0,0 +1,43 @@
+use std::collections::HashSet;
+
+use rusty_mandelbrot::location::Location;
+use rusty_mandelbrot::presets::{Preset, PRESETS};
+use rusty_mandelbrot::{calculate_mandelbrot, Config};
+
+#[test]
+fn presets_are_found_by_their_names() {
+    let names: HashSet<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+    assert_eq!(names.len(), PRESETS.len());
+    for preset in &PRESETS {
+        assert_eq!(Preset::find(preset.name), Some(preset));
+        assert!(!preset.description.is_empty());
+        // a name that can be given to --preset without quotes.
+        assert!(preset
+            .name
+            .chars()
+            .all(|character| character.is_ascii_lowercase() || character == '-'));
+    }
+    assert_eq!(Preset::find("nowhere"), None);
+}
+
+#[test]
+fn preset_locations_parse_and_validate() {
+    for preset in &PRESETS {
+        let location = &preset.location;
+        let line = location.to_string();
+        assert_eq!(line.parse::<Location>().as_ref(), Ok(location), "{}", line);
+
+        let mut config = Config::new(location.view(40, 30, 1.0), location.max_iterations);
+        config.julia = location.julia;
+        config.formula = location.formula.clone();
+        assert_eq!(config.validate(), Ok(()), "{}", preset.name);
+
+        // something to see, and not a flat colour: counts of many bands, as around the
+        // edge of the set, whether or not any of it is inside.
+        let counts: HashSet<usize> = calculate_mandelbrot(&config)
+            .into_iter()
+            .flatten()
+            .collect();
+        assert!(counts.len() >= 8, "{}", preset.name);
+    }
+}
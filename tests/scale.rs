@@ -0,0 +1,75 @@
+use rusty_mandelbrot::Scale;
+
+#[test]
+fn scales_are_monotonic() {
+    // a grid with every count once, and a few points inside the set.
+    let grid = vec![(0..100).collect::<Vec<usize>>(), vec![100; 10]];
+    for scale in Scale::ALL {
+        let scaler = scale.prepare(&grid, 100);
+        let mapped: Vec<f64> = (0..100).map(|value| scaler.map(value)).collect();
+        assert!(
+            mapped.windows(2).all(|pair| pair[0] < pair[1]),
+            "{}",
+            scale.name()
+        );
+        assert_eq!(mapped[0], 0.0, "{}", scale.name());
+        assert!(mapped[99] < 1.0, "{}", scale.name());
+        // fractional counts fall in between.
+        let smooth: Vec<f64> = [0.0, 0.5, 10.25, 10.75, 98.9]
+            .iter()
+            .map(|&value| scaler.map(value))
+            .collect();
+        assert!(smooth.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
+
+#[test]
+fn points_inside_the_set_map_to_1() {
+    let grid = vec![vec![3usize, 7, 50, 50]];
+    for scale in Scale::ALL {
+        let scaler = scale.prepare(&grid, 50);
+        assert_eq!(scaler.map(50usize), 1.0, "{}", scale.name());
+        assert_eq!(scaler.map(50.0), 1.0, "{}", scale.name());
+        assert!(scaler.map(49usize) < 1.0, "{}", scale.name());
+        assert!(scaler.map(49.99) < 1.0, "{}", scale.name());
+    }
+}
+
+#[test]
+fn histogram_spreads_the_counts_that_are_on_screen() {
+    // most points escape after 2 iterations, so they get most of the ramp between them.
+    let grid = vec![vec![2usize; 90], vec![40; 10]];
+    let scaler = Scale::Histogram.prepare(&grid, 100);
+    assert_eq!(scaler.map(2usize), 0.0);
+    assert_eq!(scaler.map(40usize), 0.9);
+    assert_eq!(scaler.map(3usize), scaler.map(39usize));
+
+    // with every escaped point at the same count, all of them land at the bottom.
+    let grid = vec![vec![7usize; 20], vec![100; 5]];
+    let scaler = Scale::Histogram.prepare(&grid, 100);
+    assert_eq!(scaler.map(7usize), 0.0);
+    assert_eq!(scaler.map(100usize), 1.0);
+
+    // and with nothing escaped at all, there is nothing to spread.
+    let scaler = Scale::Histogram.prepare(&[vec![100usize; 4]], 100);
+    assert_eq!(scaler.map(5usize), 0.0);
+    assert_eq!(scaler.map(100usize), 1.0);
+}
+
+#[test]
+fn linear_and_log_do_not_look_at_the_grid() {
+    let empty: Vec<Vec<usize>> = Vec::new();
+    let linear = Scale::Linear.prepare(&empty, 100);
+    assert_eq!(linear.map(50usize), 0.5);
+    let log = Scale::Logarithmic.prepare(&empty, 100);
+    assert!((log.map(10usize) - 11f64.ln() / 101f64.ln()).abs() < 1e-15);
+    assert!(log.map(10usize) > linear.map(10usize));
+}
+
+#[test]
+fn names_round_trip() {
+    for scale in Scale::ALL {
+        assert_eq!(Scale::from_name(scale.name()), Some(scale));
+    }
+    assert_eq!(Scale::from_name("cubic"), None);
+}
@@ -0,0 +1,244 @@
+/*
+Checks that a scene comes back unchanged from a scene file, and from the PNG it was stored in,
+and that files with unknown keys or values of the wrong type are turned away.
+*/
+
+use std::path::PathBuf;
+
+use num::complex::Complex;
+use rusty_mandelbrot::render::ascii::Ramp;
+use rusty_mandelbrot::render::png;
+use rusty_mandelbrot::scene::{self, Scene};
+use rusty_mandelbrot::{
+    calculate_mandelbrot, Bailout, Buddhabrot, Config, Fractal, Newton, Norm, Palette, Scale, View,
+};
+
+// a scene with a deep view, whose bounds only survive if every digit is written out.
+fn deep_scene() -> Scene {
+    let config = Config::new(
+        View::new(
+            -0.743_643_887_037_151,
+            -0.743_643_887_036_8,
+            0.131_825_904_205_33,
+            0.131_825_904_205_6,
+            40,
+            30,
+        ),
+        5000,
+    );
+    let mut scene = Scene {
+        config,
+        ..Scene::default()
+    };
+    scene.coloring.palette = Palette::Fire;
+    scene.coloring.smooth = true;
+    scene.output.mode = "png".to_string();
+    scene
+}
+
+#[test]
+fn scene_file_round_trip() {
+    let mut scene = deep_scene();
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+
+    scene.config.julia = Some(Complex::new(-0.123, 0.745));
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+
+    scene.config.formula = Fractal::Multibrot(2.5);
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+
+    scene.config.formula = Fractal::parse("sin(z)*c; z0 = c").unwrap();
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+
+    let mut newton =
+        Newton::from_coefficients(&[1.0.into(), 0.0.into(), (-2.0).into(), 2.0.into()]).unwrap();
+    newton.relaxation = Complex::new(0.75, 0.125);
+    newton.nova = true;
+    scene.newton = Some(newton);
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+
+    scene.newton = None;
+    scene.buddhabrot = Some(Buddhabrot {
+        samples: 123_456,
+        nebula: Some([5000, 500, 50]),
+        anti: true,
+        seed: u64::MAX - 1,
+    });
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+}
+
+#[test]
+fn scene_from_png() {
+    let scene = deep_scene();
+    let toml = scene.to_toml();
+    let points = calculate_mandelbrot(&Config::new(View::new(-2.0, 1.0, -1.0, 1.0, 12, 8), 40));
+    let mut image = Vec::new();
+    let text = [
+        ("Software", "rusty-mandelbrot"),
+        (scene::PNG_KEYWORD, &*toml),
+    ];
+    png::write_png_with_text(&points, 40, Palette::Ocean, &text, &mut image).unwrap();
+
+    let read = png::read_text(&image[..]).unwrap();
+    assert_eq!(read.len(), 2);
+    assert_eq!(
+        read[0],
+        ("Software".to_string(), "rusty-mandelbrot".to_string())
+    );
+
+    // the output is not taken from an image, so rendering it again cannot overwrite it.
+    let loaded = Scene::from_png(&image).unwrap();
+    assert_eq!(loaded.output, scene::Output::default());
+    assert_eq!(loaded.config, scene.config);
+    assert_eq!(loaded.coloring, scene.coloring);
+}
+
+#[test]
+fn every_setting_round_trips() {
+    let mut scene = deep_scene();
+    scene.config.bailout = Bailout::new(16.0, Norm::Chebyshev);
+    scene.config.interior_shortcut = false;
+    scene.config.periodicity_check = false;
+    scene.config.periodicity_tolerance = 1e-7;
+    scene.config.formula = Fractal::BurningShip;
+    scene.coloring.scale = Scale::Histogram;
+    scene.coloring.smooth = false;
+    scene.coloring.periods = true;
+    scene.coloring.ramp = Ramp::new(" .\"\\#é█").unwrap();
+    scene.coloring.threshold = 0.375;
+    scene.output.mode = "ppm".to_string();
+    scene.output.path = Some(PathBuf::from("dir with spaces/\"quoted\".ppm"));
+    assert_eq!(Scene::from_toml(&scene.to_toml()).unwrap(), scene);
+}
+
+#[test]
+fn missing_keys_keep_their_defaults() {
+    assert_eq!(Scene::from_toml("version = 1").unwrap(), Scene::default());
+
+    let scene = Scene::from_toml(
+        "# only the size\nversion = 1\n\n[view]\nwidth = 10 # columns\nheight = 5\n",
+    )
+    .unwrap();
+    assert_eq!((scene.config.view.width, scene.config.view.height), (10, 5));
+    assert_eq!(
+        scene.config.max_iterations,
+        Scene::default().config.max_iterations
+    );
+    assert_eq!(scene.coloring, Scene::default().coloring);
+}
+
+// a scene file of the current version with `lines` added in their sections.
+fn scene_with(lines: &str) -> Result<Scene, String> {
+    Scene::from_toml(&format!("version = 1\n{}\n", lines))
+}
+
+fn assert_error(lines: &str, message: &str) {
+    match scene_with(lines) {
+        Ok(_) => panic!("'{}' should not be read", lines),
+        Err(error) => assert!(
+            error.contains(message),
+            "'{}' gave '{}', expected '{}'",
+            lines,
+            error,
+            message
+        ),
+    }
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    assert_error("colour = \"red\"", "unknown key 'colour'");
+    assert_error("[view]\nzoom = 2.0", "unknown key 'zoom' in [view]");
+    assert_error("[camera]\nzoom = 2.0", "unknown key 'zoom' in [camera]");
+    // a key of another section is still unknown where it is.
+    assert_error(
+        "[coloring]\nwidth = 10",
+        "unknown key 'width' in [coloring]",
+    );
+}
+
+#[test]
+fn wrong_types_are_rejected() {
+    assert_error(
+        "[coloring]\npalette = 3",
+        "'palette' in [coloring] must be a string",
+    );
+    assert_error(
+        "[view]\nwidth = \"wide\"",
+        "'width' in [view] must be an integer",
+    );
+    assert_error(
+        "[view]\nwidth = 10.0",
+        "'width' in [view] must be an integer",
+    );
+    assert_error(
+        "[view]\nheight = -1",
+        "'height' in [view] must be 0 or more",
+    );
+    assert_error(
+        "[view]\nreal_min = true",
+        "'real_min' in [view] must be a number",
+    );
+    assert_error(
+        "[coloring]\nsmooth = 1",
+        "'smooth' in [coloring] must be true or false",
+    );
+    assert_error(
+        "[buddhabrot]\nanti = \"yes\"",
+        "'anti' in [buddhabrot] must be true or false",
+    );
+    assert_eq!(
+        Scene::from_toml("version = \"1\""),
+        Err("'version' must be an integer".to_string())
+    );
+    // integers are good numbers.
+    let scene = scene_with("[iteration]\nbailout_radius = 4").unwrap();
+    assert_eq!(scene.config.bailout.radius, 4.0);
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    assert!(Scene::from_toml("[view]\nwidth = 10").is_err());
+    assert!(Scene::from_toml("version = 99").is_err());
+    assert_error("[coloring]\npalette = \"mud\"", "unknown palette 'mud'");
+    assert_error("[coloring]\nscale = \"cubic\"", "unknown scale 'cubic'");
+    assert_error(
+        "[iteration]\nbailout_norm = \"round\"",
+        "unknown norm 'round'",
+    );
+    assert_error(
+        "[iteration]\nmax_iterations = 0",
+        "iterations must be at least 1",
+    );
+    assert_error(
+        "[iteration]\nformula = \"tricorn\"\npower = 3.0",
+        "'power' only goes with",
+    );
+    assert_error(
+        "[iteration]\njulia_real = 0.5",
+        "'julia_real' and 'julia_imaginary' go together",
+    );
+    assert_error("[newton]\nnova = true", "needs 'roots'");
+    assert_error(
+        "[buddhabrot]\nred_iterations = 10",
+        "all three channels go together",
+    );
+    assert_error(
+        "[newton]\nroots = \"1 -1\"\n[buddhabrot]\nsamples = 10",
+        "not both",
+    );
+}
+
+#[test]
+fn syntax_errors_give_the_line() {
+    assert_error(
+        "[view]\nwidth = 10\nwidth = 20",
+        "line 4: key 'width' appears twice",
+    );
+    assert_error(
+        "[view]\n[coloring]\n[view]",
+        "line 4: section [view] appears twice",
+    );
+    assert_error("[coloring]\npalette = \"fire", "line 3:");
+    assert_error("[coloring]\npalette fire", "line 3:");
+}
@@ -0,0 +1,73 @@
+use rusty_mandelbrot::render::ansi::{ColorDepth, RESET};
+use rusty_mandelbrot::render::subcell::{render_braille, render_half_blocks};
+use rusty_mandelbrot::{Palette, Scale};
+
+const MAX: usize = 10;
+
+fn braille(points: &[Vec<usize>]) -> String {
+    let mut out = Vec::new();
+    render_braille(points, MAX, Scale::Linear, 1.0, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn every_braille_dot_has_its_bit() {
+    // the dots by row and column, numbered as in the braille standard.
+    let bits = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    for (y, row) in bits.iter().enumerate() {
+        for (x, &bit) in row.iter().enumerate() {
+            let mut points = vec![vec![0; 2]; 4];
+            points[y][x] = MAX;
+            let expected = char::from_u32(0x2800 + bit).unwrap();
+            assert_eq!(
+                braille(&points),
+                format!("{}\n", expected),
+                "dot {},{}",
+                x,
+                y
+            );
+        }
+    }
+    assert_eq!(braille(&vec![vec![MAX; 2]; 4]), "\u{28FF}\n");
+    assert_eq!(braille(&vec![vec![0; 2]; 4]), "\u{2800}\n");
+}
+
+#[test]
+fn braille_pads_partial_cells() {
+    // 3 wide and 5 high: the last column and the last line of cells are only partly filled,
+    // and the missing points count as lowered dots.
+    let points = vec![vec![MAX; 3]; 5];
+    let text = braille(&points);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines, ["\u{28FF}\u{2847}", "\u{2809}\u{2801}"]);
+}
+
+#[test]
+fn half_blocks_pair_up_rows() {
+    let palette = Palette::Fire;
+    let depth = ColorDepth::TrueColor;
+    let color = |count| palette.color_iterations(count, MAX);
+    // 3 rows of 3 points: one full line, then one with only the top half.
+    let points = vec![vec![MAX, 5, 5], vec![0, 0, 0], vec![5, 5, MAX]];
+    let mut out = Vec::new();
+    render_half_blocks(&points, MAX, palette, depth, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    // escapes are only written where the colour changes.
+    let first = format!(
+        "{}{}▀{}▀▀{}",
+        depth.foreground(color(MAX)),
+        depth.background(color(0)),
+        depth.foreground(color(5)),
+        RESET
+    );
+    // the bottom half of the last line keeps the default background.
+    let last = format!(
+        "{}\x1b[49m▀▀{}▀{}",
+        depth.foreground(color(5)),
+        depth.foreground(color(MAX)),
+        RESET
+    );
+    assert_eq!(lines, [first, last]);
+}
@@ -0,0 +1,83 @@
+use num::complex::Complex;
+use rusty_mandelbrot::{Location, Palette, View};
+
+fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+}
+
+fn assert_same_bounds(a: &View, b: &View) {
+    assert_close(a.real_min, b.real_min);
+    assert_close(a.real_max, b.real_max);
+    assert_close(a.imaginary_min, b.imaginary_min);
+    assert_close(a.imaginary_max, b.imaginary_max);
+}
+
+// the height of one point in the plane divided by its width.
+fn complex_aspect(view: &View) -> f64 {
+    let point_width = (view.real_max - view.real_min) / view.width as f64;
+    let point_height = (view.imaginary_max - view.imaginary_min) / view.height as f64;
+    point_height / point_width
+}
+
+#[test]
+fn points_keep_their_shape() {
+    let center = Complex::new(-0.5, 0.25);
+    for (width, height, point_aspect) in [
+        (100, 30, 2.0),
+        (80, 80, 2.0),
+        (50, 50, 1.0),
+        (400, 100, 1.0),
+        (60, 200, 1.0),
+        (120, 40, 2.3),
+    ] {
+        for zoom in [1.0, 7.5] {
+            let view = View::from_center_zoom_aspect(center, zoom, width, height, point_aspect);
+            assert_close(complex_aspect(&view), point_aspect);
+            assert_close((view.real_min + view.real_max) / 2.0, center.re);
+            assert_close((view.imaginary_min + view.imaginary_max) / 2.0, center.im);
+
+            // at zoom 1, the default view fits, and just touches the edges along one axis.
+            let default = View::default();
+            let real =
+                (view.real_max - view.real_min) * zoom / (default.real_max - default.real_min);
+            let imaginary = (view.imaginary_max - view.imaginary_min) * zoom
+                / (default.imaginary_max - default.imaginary_min);
+            assert_close(real.min(imaginary), 1.0);
+        }
+    }
+}
+
+#[test]
+fn a_view_gives_back_its_location() {
+    for view in [
+        View::new(-1.0, 0.5, -0.25, 0.75, 300, 200),
+        View::new(-1.0, 1.0, -0.5, 0.5, 200, 100),
+        View::new(-0.2, 0.2, 0.1, 1.3, 50, 150),
+        View::default(),
+    ] {
+        let location = Location::from_view(&view, 500, Palette::Fire);
+        assert_eq!(location.max_iterations, 500);
+        // the points of the default view are not square, so they are given the shape they have.
+        let point_aspect = complex_aspect(&view);
+        assert_same_bounds(&location.view(view.width, view.height, point_aspect), &view);
+    }
+}
+
+#[test]
+fn fitting_shows_the_whole_view() {
+    let view = View::new(-1.0, 0.5, -0.25, 0.75, 300, 200);
+    for (width, height, point_aspect) in [(100, 100, 1.0), (400, 100, 1.0), (80, 24, 2.0)] {
+        let fitted = Location::fitting(&view, 500, Palette::Fire).view(width, height, point_aspect);
+        assert!(fitted.real_min <= view.real_min + 1e-12);
+        assert!(fitted.real_max >= view.real_max - 1e-12);
+        assert!(fitted.imaginary_min <= view.imaginary_min + 1e-12);
+        assert!(fitted.imaginary_max >= view.imaginary_max - 1e-12);
+        // and no more than it has to: it touches the view along one axis.
+        let touches_real = (fitted.real_max - fitted.real_min - 1.5).abs() < 1e-12;
+        let touches_imaginary = (fitted.imaginary_max - fitted.imaginary_min - 1.0).abs() < 1e-12;
+        assert!(touches_real || touches_imaginary, "{:?}", fitted);
+    }
+    // on a grid of the view's own shape, it is the view itself.
+    let same = Location::fitting(&view, 500, Palette::Fire).view(300, 200, 1.0);
+    assert_same_bounds(&same, &view);
+}